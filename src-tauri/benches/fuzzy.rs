@@ -0,0 +1,77 @@
+// Compares the fuzzy scorer against the Jaro-Winkler scorer it replaced.
+// Run with `cargo bench --bench fuzzy`.
+
+#[allow(non_snake_case, dead_code)]
+#[path = "../src/fuzzyLib.rs"]
+mod fuzzyLib;
+
+use std::time::Instant;
+
+const CANDIDATES: &[&str] = &[
+    "/Applications/Visual Studio Code.app",
+    "/Applications/Google Chrome.app",
+    "/Applications/Safari.app",
+    "/Applications/System Settings.app",
+    "/Applications/Activity Monitor.app",
+    "/Users/me/projects/astrolaunch/src-tauri/src/main.rs",
+    "/Users/me/projects/astrolaunch/src-tauri/src/searchFilesLib.rs",
+    "/Users/me/projects/astrolaunch/src/App.tsx",
+    "/Users/me/Documents/Quarterly Report 2024.pdf",
+    "/Users/me/Documents/quarterly_report_draft.docx",
+    "/Users/me/Downloads/VSCode-darwin-universal.zip",
+    "/Users/me/Pictures/Screenshot 2024-06-01 at 10.12.44.png",
+];
+
+const QUERIES: &[&str] = &[
+    "vsc", "gc", "sfl", "src/main", "qr2024", "actmon", "app.tsx",
+];
+
+fn jaro_rank(query: &str) -> Vec<&'static str> {
+    let mut scored: Vec<(f64, &str)> = CANDIDATES
+        .iter()
+        .filter_map(|path| {
+            let name = std::path::Path::new(path).file_name()?.to_str()?;
+            let score = strsim::jaro_winkler(query, name);
+            (score > 0.6).then_some((score, *path))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    scored.into_iter().map(|(_, p)| p).take(3).collect()
+}
+
+fn fuzzy_rank(query: &str) -> Vec<&'static str> {
+    let mut scored: Vec<(i64, &str)> = CANDIDATES
+        .iter()
+        .filter_map(|path| fuzzyLib::fuzzy_match_path(query, path).map(|m| (m.score, *path)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, p)| p).take(3).collect()
+}
+
+fn time<F: Fn(&str) -> Vec<&'static str>>(label: &str, rank: F) {
+    const ITERATIONS: u32 = 2_000;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for query in QUERIES {
+            std::hint::black_box(rank(query));
+        }
+    }
+    let per_query = start.elapsed() / (ITERATIONS * QUERIES.len() as u32);
+    println!(
+        "{:<14} {:>10.2?} per query over {} candidates",
+        label,
+        per_query,
+        CANDIDATES.len()
+    );
+}
+
+fn main() {
+    for query in QUERIES {
+        println!("query {:?}", query);
+        println!("  jaro-winkler: {:?}", jaro_rank(query));
+        println!("  fuzzy:        {:?}", fuzzy_rank(query));
+    }
+    println!();
+    time("jaro-winkler", jaro_rank);
+    time("fuzzy", fuzzy_rank);
+}
@@ -1,110 +1,174 @@
+use crate::commandLib::CommandRunner;
+#[cfg(target_os = "linux")]
+use crate::fileActionsLib::mime_associations;
+use crate::frecencyLib::{self, UsageKind};
+use crate::fuzzyLib;
+use crate::platformLib::{self, AppLauncher};
 use crate::AppInfo;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-#[tauri::command]
-pub fn list_apps() -> Result<Vec<AppInfo>, String> {
-    #[cfg(target_os = "macos")]
-    {
-        let app_dirs = vec![
-            PathBuf::from("/Applications"),
-            PathBuf::from("/System/Applications"),
-        ];
+// App discovery and launching: `/Applications` and `open` on macOS, the Start
+// menu and `start` on Windows, `.desktop` entries and `gtk-launch` on Linux
+pub struct SystemApps {
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl SystemApps {
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        SystemApps { runner }
+    }
+}
+
+// Files in `dir` with the given extension, as (file stem, path)
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?.to_string();
+            Some((stem, path))
+        })
+        .collect()
+}
+
+// The untranslated name of a `.desktop` entry that belongs in an app list
+#[cfg(target_os = "linux")]
+fn launchable_entry_name(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let entry = mime_associations::parse_ini(&contents).remove("Desktop Entry")?;
+    let is = |key: &str, value: &str| entry.get(key).map(String::as_str) == Some(value);
+    if !is("Type", "Application") || is("NoDisplay", "true") || is("Hidden", "true") {
+        return None;
+    }
+    entry.get("Name").cloned()
+}
+
+impl AppLauncher for SystemApps {
+    fn installed_apps(&self) -> Vec<AppInfo> {
         let mut apps = Vec::new();
-        let mut seen_paths = std::collections::HashSet::new();
-
-        for dir in app_dirs {
-            if let Ok(entries) = fs::read_dir(dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if let Some(ext) = path.extension() {
-                        if ext == "app" {
-                            let path_str = path.display().to_string();
-                            if seen_paths.insert(path_str.clone()) {
-                                if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
-                                    apps.push(AppInfo {
-                                        name: name.to_string(),
-                                        path: path_str,
-                                    });
-                                }
-                            }
-                        }
-                    }
+        let mut seen = HashSet::new();
+
+        #[cfg(target_os = "macos")]
+        for dir in ["/Applications", "/System/Applications"] {
+            for (name, path) in files_with_extension(Path::new(dir), "app") {
+                let path = path.display().to_string();
+                if seen.insert(path.clone()) {
+                    apps.push(AppInfo { name, path });
                 }
             }
         }
 
-        Ok(apps)
-    }
+        #[cfg(target_os = "windows")]
+        {
+            let start_menu = Path::new(r"C:\ProgramData\Microsoft\Windows\Start Menu\Programs");
+            for (name, path) in files_with_extension(start_menu, "lnk") {
+                let path = path.display().to_string();
+                if seen.insert(path.clone()) {
+                    apps.push(AppInfo { name, path });
+                }
+            }
+        }
 
-    #[cfg(target_os = "windows")]
-    {
-        let start_menu = PathBuf::from(r"C:\ProgramData\Microsoft\Windows\Start Menu\Programs");
-        let mut apps = Vec::new();
-        if let Ok(entries) = fs::read_dir(start_menu) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(ext) = path.extension() {
-                    if ext == "lnk" {
-                        if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
-                            apps.push(AppInfo {
-                                name: name.to_string(),
-                                path: path.display().to_string(),
-                            });
-                        }
+        #[cfg(target_os = "linux")]
+        {
+            // Earlier directories hide entries with the same id
+            for dir in mime_associations::application_dirs() {
+                for (id, path) in files_with_extension(&dir, "desktop") {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                    if let Some(name) = launchable_entry_name(&path) {
+                        apps.push(AppInfo {
+                            name,
+                            path: path.display().to_string(),
+                        });
                     }
                 }
             }
         }
-        Ok(apps)
+
+        apps
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    Err("Unsupported OS".to_string())
-}
+    fn launch(&self, app: &str) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let result = self.runner.output("open", &["-a", app]);
+        #[cfg(target_os = "windows")]
+        let result = self.runner.output("cmd", &["/C", "start", "", app]);
+        #[cfg(target_os = "linux")]
+        let result = {
+            // gtk-launch takes the desktop file id, not its path
+            let id = Path::new(app)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(app);
+            self.runner.output("gtk-launch", &[id])
+        };
 
-// Command to launch an app by name or path
-#[tauri::command]
-pub fn launch_app(app_name: &str) -> Result<String, String> {
-    #[cfg(target_os = "macos")]
-    {
-        let output = Command::new("open")
-            .args(&["-a", app_name])
-            .output()
-            .map_err(|e| format!("Failed to execute: {}", e))?;
-
-        if output.status.success() {
-            Ok(format!("Launched {}", app_name))
-        } else {
-            Err(format!(
-                "Failed to launch {}: {}",
-                app_name,
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
+        result
+            .map(|_| ())
+            .map_err(|e| format!("Failed to launch {}: {}", app, e))
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        let output = Command::new("cmd")
-            .args(&["/C", "start", "", app_name])
-            .output()
-            .map_err(|e| format!("Failed to execute: {}", e))?;
-
-        if output.status.success() {
-            Ok(format!("Launched {}", app_name))
-        } else {
-            Err(format!(
-                "Failed to launch {}: {}",
-                app_name,
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
+    fn open_url(&self, url: &str) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let result = self.runner.output("open", &[url]);
+        #[cfg(target_os = "windows")]
+        let result = self.runner.output("cmd", &["/C", "start", "", url]);
+        #[cfg(target_os = "linux")]
+        let result = self.runner.output("xdg-open", &[url]);
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    {
-        Err("Unsupported OS".into())
+        result.map(|_| ())
     }
 }
+
+#[tauri::command]
+pub fn list_apps() -> Result<Vec<AppInfo>, String> {
+    let apps = platformLib::current().apps.installed_apps();
+    Ok(frecencyLib::sort_by_frecency(UsageKind::App, apps, |app| {
+        app.path.as_str()
+    }))
+}
+
+#[derive(Serialize)]
+pub struct AppMatch {
+    name: String,
+    path: String,
+    score: i64,
+    indices: Vec<usize>,
+}
+
+// Fuzzy-filter the installed apps by name, best match first, boosted by frecency
+#[tauri::command]
+pub fn search_apps(query: &str) -> Result<Vec<AppMatch>, String> {
+    let apps = list_apps()?;
+
+    let mut matches: Vec<AppMatch> = fuzzyLib::rank_by(query, apps, |app| app.name.as_str())
+        .into_iter()
+        .map(|(app, m)| AppMatch {
+            score: m.score + frecencyLib::rank_boost(UsageKind::App, &app.path),
+            name: app.name,
+            path: app.path,
+            indices: m.indices,
+        })
+        .collect();
+    matches.sort_by_key(|m| Reverse(m.score));
+    Ok(matches)
+}
+
+// Command to launch an app by name or path
+#[tauri::command]
+pub fn launch_app(app_name: &str) -> Result<String, String> {
+    frecencyLib::track(UsageKind::App, app_name);
+    platformLib::current().apps.launch(app_name)?;
+    Ok(format!("Launched {}", app_name))
+}
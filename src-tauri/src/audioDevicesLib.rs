@@ -0,0 +1,765 @@
+// Audio output and input devices: listing them, picking the default ones and
+// per-device volume. Each platform implements `AudioDevices`; on Linux that is
+// `pulseAudioLib::PulseAudio`.
+
+#[cfg(not(target_os = "linux"))]
+use crate::commandLib::CommandRunner;
+#[cfg(not(target_os = "linux"))]
+use crate::mediaLib::VolumeState;
+use crate::platformLib;
+#[cfg(not(target_os = "linux"))]
+use crate::platformLib::VolumeControl;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_os = "linux"))]
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Output,
+    Input,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AudioDevice {
+    // Platform identifier to pass back to the other commands
+    pub id: String,
+    pub name: String,
+    pub kind: DeviceKind,
+    pub is_default: bool,
+    // Unknown when the platform can only read the default device
+    pub volume: Option<u8>,
+    pub muted: Option<bool>,
+}
+
+pub trait AudioDevices: Send + Sync {
+    fn list_devices(&self) -> Result<Vec<AudioDevice>, String>;
+    fn set_default_device(&self, kind: DeviceKind, id: &str) -> Result<(), String>;
+    fn set_device_volume(&self, kind: DeviceKind, id: &str, percent: u8) -> Result<(), String>;
+    fn set_device_muted(&self, kind: DeviceKind, id: &str, muted: bool) -> Result<(), String>;
+}
+
+// Switching devices needs the `SwitchAudioSource` CLI
+// (`brew install switchaudio-osx`); volume goes through AppleScript, which
+// only reaches the current devices.
+#[cfg(target_os = "macos")]
+pub struct MacAudio {
+    runner: Arc<dyn CommandRunner>,
+    // The "muted" input and the level to restore when it is unmuted
+    muted_input: std::sync::Mutex<Option<(String, u8)>>,
+}
+
+#[cfg(target_os = "macos")]
+impl MacAudio {
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        MacAudio {
+            runner,
+            muted_input: std::sync::Mutex::new(None),
+        }
+    }
+
+    fn switch_audio(&self, args: &[&str]) -> Result<String, String> {
+        self.runner
+            .output("SwitchAudioSource", args)
+            .map_err(|e| format!("{} (install it with `brew install switchaudio-osx`)", e))
+    }
+
+    fn osascript(&self, script: &str) -> Result<String, String> {
+        self.runner.output("osascript", &["-e", script])
+    }
+
+    // `get volume settings`, e.g. "output volume:50, input volume:75, ..."
+    fn volume_settings(&self) -> Result<std::collections::HashMap<String, String>, String> {
+        Ok(self
+            .osascript("get volume settings")?
+            .split(',')
+            .filter_map(|pair| pair.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect())
+    }
+
+    fn current(&self, kind: DeviceKind) -> Result<String, String> {
+        self.switch_audio(&["-c", "-t", mac_type(kind)])
+    }
+
+    fn ensure_current(&self, kind: DeviceKind, id: &str) -> Result<(), String> {
+        if self.current(kind)? == id {
+            Ok(())
+        } else {
+            Err("macOS can only change the volume of the current device".into())
+        }
+    }
+}
+
+// Input level for unmuting an input that was already at zero
+#[cfg(target_os = "macos")]
+const MAC_DEFAULT_INPUT_LEVEL: u8 = 75;
+
+#[cfg(target_os = "macos")]
+fn mac_type(kind: DeviceKind) -> &'static str {
+    match kind {
+        DeviceKind::Output => "output",
+        DeviceKind::Input => "input",
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl AudioDevices for MacAudio {
+    fn list_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        #[derive(Deserialize)]
+        struct Listed {
+            name: String,
+        }
+
+        let settings = self.volume_settings().unwrap_or_default();
+        let mut devices = Vec::new();
+        for kind in [DeviceKind::Output, DeviceKind::Input] {
+            let current = self.current(kind)?;
+            // One JSON object per line
+            let listed = self.switch_audio(&["-a", "-t", mac_type(kind), "-f", "json"])?;
+            for line in listed.lines() {
+                let Ok(Listed { name }) = serde_json::from_str(line) else {
+                    continue;
+                };
+                let is_default = name == current;
+                let volume_key = format!("{} volume", mac_type(kind));
+                let volume: Option<u8> = is_default
+                    .then(|| settings.get(&volume_key)?.parse().ok())
+                    .flatten();
+                let muted = match kind {
+                    DeviceKind::Output if is_default => {
+                        settings.get("output muted").map(|m| m == "true")
+                    }
+                    // Inputs have no mute; a zero level stands in for it
+                    DeviceKind::Input => volume.map(|level| level == 0),
+                    _ => None,
+                };
+                devices.push(AudioDevice {
+                    volume,
+                    muted,
+                    id: name.clone(),
+                    name,
+                    kind,
+                    is_default,
+                });
+            }
+        }
+        Ok(devices)
+    }
+
+    fn set_default_device(&self, kind: DeviceKind, id: &str) -> Result<(), String> {
+        self.switch_audio(&["-t", mac_type(kind), "-s", id])
+            .map(|_| ())
+    }
+
+    fn set_device_volume(&self, kind: DeviceKind, id: &str, percent: u8) -> Result<(), String> {
+        self.ensure_current(kind, id)?;
+        self.osascript(&format!("set volume {} volume {}", mac_type(kind), percent))?;
+        // A level picked by hand replaces the one saved by muting
+        if kind == DeviceKind::Input {
+            *self.muted_input.lock().unwrap() = None;
+        }
+        Ok(())
+    }
+
+    fn set_device_muted(&self, kind: DeviceKind, id: &str, muted: bool) -> Result<(), String> {
+        self.ensure_current(kind, id)?;
+        if kind == DeviceKind::Output {
+            return self
+                .osascript(&format!("set volume output muted {}", muted))
+                .map(|_| ());
+        }
+
+        // Inputs have no mute, so park the level at zero and put it back after
+        let level: u8 = self
+            .volume_settings()?
+            .get("input volume")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let mut saved = self.muted_input.lock().unwrap();
+        // A level saved while another input was current doesn't apply to this one
+        let previous = saved
+            .take()
+            .filter(|(input, _)| input == id)
+            .map(|(_, level)| level);
+        let target = match (muted, level) {
+            (true, 0) => {
+                *saved = previous.map(|level| (id.to_string(), level));
+                return Ok(());
+            }
+            (true, level) => {
+                *saved = Some((id.to_string(), level));
+                0
+            }
+            (false, 0) => previous.unwrap_or(MAC_DEFAULT_INPUT_LEVEL),
+            (false, _) => return Ok(()),
+        };
+        let set = self.osascript(&format!("set volume input volume {}", target));
+        if set.is_err() {
+            *saved = previous.map(|level| (id.to_string(), level));
+        }
+        set.map(|_| ())
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl VolumeControl for MacAudio {
+    fn volume(&self) -> Result<VolumeState, String> {
+        let settings = self.volume_settings()?;
+        // Outputs without a software volume (e.g. HDMI) report "missing value"
+        let level = settings
+            .get("output volume")
+            .and_then(|v| v.parse().ok())
+            .ok_or("The current output device has no adjustable volume")?;
+        Ok(VolumeState {
+            level,
+            muted: settings.get("output muted").is_some_and(|m| m == "true"),
+        })
+    }
+
+    fn set_volume(&self, percent: u8) -> Result<(), String> {
+        self.osascript(&format!("set volume output volume {}", percent))
+            .map(|_| ())
+            .map_err(|e| format!("Failed to set volume on macOS: {}", e))
+    }
+
+    fn set_muted(&self, muted: bool) -> Result<(), String> {
+        self.osascript(&format!("set volume output muted {}", muted))
+            .map(|_| ())
+    }
+}
+
+// Core Audio for PowerShell. `flow` 0 is output, 1 is input; an empty `id`
+// means the default device of that flow.
+#[cfg(target_os = "windows")]
+const WINDOWS_AUDIO: &str = r#"
+Add-Type -TypeDefinition @'
+using System;
+using System.Collections.Generic;
+using System.Runtime.InteropServices;
+[Guid("5CDF2C82-841E-4546-9722-0CF74078229A"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IAudioEndpointVolume {
+    int RegisterControlChangeNotify(); int UnregisterControlChangeNotify(); int GetChannelCount(); int SetMasterVolumeLevel();
+    int SetMasterVolumeLevelScalar(float level, Guid context);
+    int GetMasterVolumeLevel();
+    int GetMasterVolumeLevelScalar(out float level);
+    int SetChannelVolumeLevel(); int SetChannelVolumeLevelScalar(); int GetChannelVolumeLevel(); int GetChannelVolumeLevelScalar();
+    int SetMute([MarshalAs(UnmanagedType.Bool)] bool mute, Guid context);
+    int GetMute([MarshalAs(UnmanagedType.Bool)] out bool mute);
+}
+[StructLayout(LayoutKind.Sequential)] struct PropertyKey { public Guid fmtid; public int pid; }
+[StructLayout(LayoutKind.Explicit)] struct PropVariant { [FieldOffset(0)] public short vt; [FieldOffset(8)] public IntPtr value; }
+[Guid("886D8EEB-8CF2-4446-8D02-CDBA1DBDCF99"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IPropertyStore { int GetCount(out int count); int GetAt(int index, out PropertyKey key); int GetValue(ref PropertyKey key, out PropVariant value); }
+[Guid("D666063F-1587-4E43-81F1-B948E807363F"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IMMDevice {
+    int Activate(ref Guid id, int context, IntPtr parameters, [MarshalAs(UnmanagedType.IUnknown)] out object endpoint);
+    int OpenPropertyStore(int access, out IPropertyStore store);
+    int GetId([MarshalAs(UnmanagedType.LPWStr)] out string id);
+}
+[Guid("0BD7A1BE-7A1A-44DB-8397-CC5392387B5E"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IMMDeviceCollection { int GetCount(out int count); int Item(int index, out IMMDevice device); }
+[Guid("A95664D2-9614-4F35-A746-DE8DB63617E6"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IMMDeviceEnumerator {
+    int EnumAudioEndpoints(int flow, int state, out IMMDeviceCollection devices);
+    int GetDefaultAudioEndpoint(int flow, int role, out IMMDevice device);
+    int GetDevice([MarshalAs(UnmanagedType.LPWStr)] string id, out IMMDevice device);
+}
+// Undocumented, but it is how the Sound control panel switches devices
+[Guid("F8679F50-850A-41CF-9C72-430F290290C8"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IPolicyConfig {
+    int GetMixFormat(); int GetDeviceFormat(); int ResetDeviceFormat(); int SetDeviceFormat(); int GetProcessingPeriod();
+    int SetProcessingPeriod(); int GetShareMode(); int SetShareMode(); int GetPropertyValue(); int SetPropertyValue();
+    int SetDefaultEndpoint([MarshalAs(UnmanagedType.LPWStr)] string id, int role);
+}
+[ComImport, Guid("BCDE0395-E52F-467C-8E3D-C4579291692E")] class MMDeviceEnumerator { }
+[ComImport, Guid("870AF99C-171D-4F9E-AF0D-E63DF40C2BC9")] class PolicyConfigClient { }
+public static class AstroAudio {
+    static IMMDeviceEnumerator Enumerator() { return (IMMDeviceEnumerator)new MMDeviceEnumerator(); }
+    static IMMDevice Device(int flow, string id) {
+        IMMDevice device;
+        if (String.IsNullOrEmpty(id)) Marshal.ThrowExceptionForHR(Enumerator().GetDefaultAudioEndpoint(flow, 1, out device));
+        else Marshal.ThrowExceptionForHR(Enumerator().GetDevice(id, out device));
+        return device;
+    }
+    static IAudioEndpointVolume Endpoint(int flow, string id) {
+        var iid = typeof(IAudioEndpointVolume).GUID;
+        object endpoint;
+        Marshal.ThrowExceptionForHR(Device(flow, id).Activate(ref iid, 23, IntPtr.Zero, out endpoint));
+        return (IAudioEndpointVolume)endpoint;
+    }
+    static string Id(IMMDevice device) { string id; Marshal.ThrowExceptionForHR(device.GetId(out id)); return id; }
+    static string Name(IMMDevice device) {
+        IPropertyStore store;
+        Marshal.ThrowExceptionForHR(device.OpenPropertyStore(0, out store));
+        var key = new PropertyKey { fmtid = new Guid("A45C254E-DF1C-4EFD-8020-67D146A850E0"), pid = 14 };
+        PropVariant value;
+        Marshal.ThrowExceptionForHR(store.GetValue(ref key, out value));
+        return Marshal.PtrToStringUni(value.value);
+    }
+    public static string State(int flow, string id) {
+        float level; bool mute;
+        var endpoint = Endpoint(flow, id);
+        Marshal.ThrowExceptionForHR(endpoint.GetMasterVolumeLevelScalar(out level));
+        Marshal.ThrowExceptionForHR(endpoint.GetMute(out mute));
+        return Math.Round(level * 100) + " " + mute;
+    }
+    public static void SetLevel(int flow, string id, float level) { Marshal.ThrowExceptionForHR(Endpoint(flow, id).SetMasterVolumeLevelScalar(level, Guid.Empty)); }
+    public static void SetMute(int flow, string id, bool mute) { Marshal.ThrowExceptionForHR(Endpoint(flow, id).SetMute(mute, Guid.Empty)); }
+    // One active device per line: flow, default, level, muted, id, name
+    public static string List() {
+        var lines = new List<string>();
+        for (int flow = 0; flow < 2; flow++) {
+            string current = "";
+            try { current = Id(Device(flow, "")); } catch (COMException) { }
+            IMMDeviceCollection devices;
+            Marshal.ThrowExceptionForHR(Enumerator().EnumAudioEndpoints(flow, 1, out devices));
+            int count;
+            Marshal.ThrowExceptionForHR(devices.GetCount(out count));
+            for (int i = 0; i < count; i++) {
+                IMMDevice device;
+                Marshal.ThrowExceptionForHR(devices.Item(i, out device));
+                var id = Id(device);
+                lines.Add(flow + "\t" + (id == current) + "\t" + State(flow, id).Replace(' ', '\t') + "\t" + id + "\t" + Name(device));
+            }
+        }
+        return String.Join("\n", lines);
+    }
+    // Console, multimedia and communications roles all follow the new device
+    public static void SetDefault(string id) {
+        var policy = (IPolicyConfig)new PolicyConfigClient();
+        for (int role = 0; role < 3; role++) Marshal.ThrowExceptionForHR(policy.SetDefaultEndpoint(id, role));
+    }
+}
+'@
+"#;
+
+#[cfg(target_os = "windows")]
+pub struct WindowsAudio {
+    runner: Arc<dyn CommandRunner>,
+}
+
+#[cfg(target_os = "windows")]
+impl WindowsAudio {
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        WindowsAudio { runner }
+    }
+
+    /// Runs `command` after loading `AstroAudio` and returns its output.
+    pub fn run(&self, command: &str) -> Result<String, String> {
+        let script = format!("{}{}", WINDOWS_AUDIO, command);
+        self.runner
+            .output(
+                "powershell",
+                &["-NoProfile", "-NonInteractive", "-Command", &script],
+            )
+            .map_err(|e| format!("Failed to control audio on Windows: {}", e))
+    }
+}
+
+// A PowerShell single-quoted string
+#[cfg(target_os = "windows")]
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(target_os = "windows")]
+fn flow(kind: DeviceKind) -> u8 {
+    match kind {
+        DeviceKind::Output => 0,
+        DeviceKind::Input => 1,
+    }
+}
+
+/// Parses `AstroAudio::List` output.
+#[cfg(target_os = "windows")]
+pub fn parse_windows_devices(output: &str) -> Vec<AudioDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_end_matches('\r').splitn(6, '\t');
+            let kind = match fields.next()? {
+                "0" => DeviceKind::Output,
+                "1" => DeviceKind::Input,
+                _ => return None,
+            };
+            let is_default = fields.next()?.eq_ignore_ascii_case("true");
+            let volume = fields.next()?.parse().ok();
+            let muted = Some(fields.next()?.eq_ignore_ascii_case("true"));
+            Some(AudioDevice {
+                id: fields.next()?.to_string(),
+                name: fields.next()?.to_string(),
+                kind,
+                is_default,
+                volume,
+                muted,
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+impl AudioDevices for WindowsAudio {
+    fn list_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        Ok(parse_windows_devices(&self.run("[AstroAudio]::List()")?))
+    }
+
+    // Windows ids already tell outputs and inputs apart
+    fn set_default_device(&self, _kind: DeviceKind, id: &str) -> Result<(), String> {
+        self.run(&format!("[AstroAudio]::SetDefault({})", quote(id)))
+            .map(|_| ())
+    }
+
+    fn set_device_volume(&self, kind: DeviceKind, id: &str, percent: u8) -> Result<(), String> {
+        self.run(&format!(
+            "[AstroAudio]::SetLevel({}, {}, {})",
+            flow(kind),
+            quote(id),
+            percent as f32 / 100.0
+        ))
+        .map(|_| ())
+    }
+
+    fn set_device_muted(&self, kind: DeviceKind, id: &str, muted: bool) -> Result<(), String> {
+        self.run(&format!(
+            "[AstroAudio]::SetMute({}, {}, ${})",
+            flow(kind),
+            quote(id),
+            muted
+        ))
+        .map(|_| ())
+    }
+}
+
+// Parses `AstroAudio::State` output, e.g. "35 False"
+#[cfg(target_os = "windows")]
+fn parse_windows_state(output: &str) -> Result<VolumeState, String> {
+    let (level, muted) = output
+        .split_once(' ')
+        .ok_or_else(|| format!("Unexpected volume output '{}'", output))?;
+    Ok(VolumeState {
+        level: level
+            .parse()
+            .map_err(|_| format!("Unexpected volume '{}'", level))?,
+        muted: muted.eq_ignore_ascii_case("true"),
+    })
+}
+
+#[cfg(target_os = "windows")]
+impl VolumeControl for WindowsAudio {
+    fn volume(&self) -> Result<VolumeState, String> {
+        parse_windows_state(&self.run("[AstroAudio]::State(0, '')")?)
+    }
+
+    fn set_volume(&self, percent: u8) -> Result<(), String> {
+        self.run(&format!(
+            "[AstroAudio]::SetLevel(0, '', {})",
+            percent as f32 / 100.0
+        ))
+        .map(|_| ())
+    }
+
+    fn set_muted(&self, muted: bool) -> Result<(), String> {
+        self.run(&format!("[AstroAudio]::SetMute(0, '', ${})", muted))
+            .map(|_| ())
+    }
+}
+
+fn find_device(
+    audio: &dyn AudioDevices,
+    kind: DeviceKind,
+    id: &str,
+) -> Result<AudioDevice, String> {
+    audio
+        .list_devices()?
+        .into_iter()
+        .find(|device| device.kind == kind && device.id == id)
+        .ok_or_else(|| format!("No audio device '{}'", id))
+}
+
+pub fn default_device(audio: &dyn AudioDevices, kind: DeviceKind) -> Result<AudioDevice, String> {
+    audio
+        .list_devices()?
+        .into_iter()
+        .find(|device| device.kind == kind && device.is_default)
+        .ok_or_else(|| match kind {
+            DeviceKind::Output => "No audio output device".to_string(),
+            DeviceKind::Input => "No microphone found".to_string(),
+        })
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> Result<Vec<AudioDevice>, String> {
+    platformLib::current().audio_devices.list_devices()
+}
+
+#[tauri::command]
+pub fn set_default_output(id: String) -> Result<(), String> {
+    platformLib::current()
+        .audio_devices
+        .set_default_device(DeviceKind::Output, &id)
+}
+
+#[tauri::command]
+pub fn set_default_input(id: String) -> Result<(), String> {
+    platformLib::current()
+        .audio_devices
+        .set_default_device(DeviceKind::Input, &id)
+}
+
+#[tauri::command]
+pub fn set_device_volume(kind: DeviceKind, id: String, volume: u8) -> Result<AudioDevice, String> {
+    if volume > 100 {
+        return Err("Volume must be between 0 and 100".into());
+    }
+    let audio = platformLib::current().audio_devices.clone();
+    audio.set_device_volume(kind, &id, volume)?;
+    find_device(audio.as_ref(), kind, &id)
+}
+
+#[tauri::command]
+pub fn set_device_mute(kind: DeviceKind, id: String, muted: bool) -> Result<AudioDevice, String> {
+    let audio = platformLib::current().audio_devices.clone();
+    audio.set_device_muted(kind, &id, muted)?;
+    find_device(audio.as_ref(), kind, &id)
+}
+
+#[cfg(all(test, any(target_os = "macos", target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::commandLib::FakeRunner;
+
+    #[cfg(target_os = "macos")]
+    fn mac_audio(settings: &str) -> (Arc<FakeRunner>, MacAudio) {
+        let runner = Arc::new(FakeRunner::default());
+        runner
+            .respond("osascript -e get volume settings", settings)
+            .respond("SwitchAudioSource -c -t output", "Speakers")
+            .respond("SwitchAudioSource -c -t input", "Microphone")
+            .respond(
+                "SwitchAudioSource -a -t output -f json",
+                "{\"name\": \"Speakers\", \"type\": \"output\", \"id\": \"55\"}\n\
+                 {\"name\": \"Display\", \"type\": \"output\", \"id\": \"62\"}",
+            )
+            .respond(
+                "SwitchAudioSource -a -t input -f json",
+                "{\"name\": \"Microphone\", \"type\": \"input\", \"id\": \"48\"}",
+            );
+        (runner.clone(), MacAudio::new(runner))
+    }
+
+    #[cfg(target_os = "macos")]
+    fn last_script(runner: &FakeRunner) -> String {
+        runner.calls().last().unwrap().last().unwrap().clone()
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn mac_lists_devices_with_levels_for_the_current_ones() {
+        let (_, audio) =
+            mac_audio("output volume:50, input volume:0, alert volume:100, output muted:true");
+        let summary: Vec<(String, DeviceKind, bool, Option<u8>, Option<bool>)> = audio
+            .list_devices()
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.id, d.kind, d.is_default, d.volume, d.muted))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "Speakers".into(),
+                    DeviceKind::Output,
+                    true,
+                    Some(50),
+                    Some(true)
+                ),
+                ("Display".into(), DeviceKind::Output, false, None, None),
+                (
+                    "Microphone".into(),
+                    DeviceKind::Input,
+                    true,
+                    Some(0),
+                    Some(true)
+                ),
+            ]
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn mac_only_changes_the_current_device() {
+        let (runner, audio) = mac_audio("output volume:50, input volume:60, output muted:false");
+        assert!(audio
+            .set_device_volume(DeviceKind::Output, "Display", 20)
+            .is_err());
+        audio
+            .set_device_volume(DeviceKind::Output, "Speakers", 20)
+            .unwrap();
+        assert_eq!(last_script(&runner), "set volume output volume 20");
+        audio
+            .set_device_muted(DeviceKind::Output, "Speakers", true)
+            .unwrap();
+        assert_eq!(last_script(&runner), "set volume output muted true");
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn mac_input_mute_restores_the_previous_level() {
+        let (runner, audio) = mac_audio("input volume:60");
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", true)
+            .unwrap();
+        assert_eq!(last_script(&runner), "set volume input volume 0");
+
+        runner.respond("osascript -e get volume settings", "input volume:0");
+        // Muting again must not replace the saved level with zero
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", true)
+            .unwrap();
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", false)
+            .unwrap();
+        assert_eq!(last_script(&runner), "set volume input volume 60");
+
+        // Once restored, the saved level is gone
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", false)
+            .unwrap();
+        assert_eq!(
+            last_script(&runner),
+            format!("set volume input volume {}", MAC_DEFAULT_INPUT_LEVEL)
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn mac_input_level_is_not_restored_on_another_device_or_after_a_change() {
+        let (runner, audio) = mac_audio("input volume:60");
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", true)
+            .unwrap();
+        audio
+            .set_device_volume(DeviceKind::Input, "Microphone", 30)
+            .unwrap();
+        runner.respond("osascript -e get volume settings", "input volume:0");
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", false)
+            .unwrap();
+        assert_eq!(
+            last_script(&runner),
+            format!("set volume input volume {}", MAC_DEFAULT_INPUT_LEVEL)
+        );
+
+        runner.respond("osascript -e get volume settings", "input volume:60");
+        audio
+            .set_device_muted(DeviceKind::Input, "Microphone", true)
+            .unwrap();
+        runner
+            .respond("SwitchAudioSource -c -t input", "Headset")
+            .respond("osascript -e get volume settings", "input volume:0");
+        audio
+            .set_device_muted(DeviceKind::Input, "Headset", false)
+            .unwrap();
+        assert_eq!(
+            last_script(&runner),
+            format!("set volume input volume {}", MAC_DEFAULT_INPUT_LEVEL)
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    fn powershell(command: &str) -> String {
+        format!(
+            "powershell -NoProfile -NonInteractive -Command {}{}",
+            WINDOWS_AUDIO, command
+        )
+    }
+
+    #[cfg(target_os = "windows")]
+    fn last_command(runner: &FakeRunner) -> String {
+        let script = runner.calls().last().unwrap().last().unwrap().clone();
+        script[WINDOWS_AUDIO.len()..].to_string()
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn windows_lists_devices() {
+        let runner = Arc::new(FakeRunner::default());
+        runner.respond(
+            &powershell("[AstroAudio]::List()"),
+            "0\tTrue\t35\tFalse\t{0.0.0.00000000}.{a1}\tSpeakers (Realtek Audio)\r\n\
+             1\tFalse\t80\tTrue\t{0.0.1.00000000}.{b2}\tMicrophone\tArray\r\n\
+             2\tTrue\t0\tFalse\tbad\tline",
+        );
+        let audio = WindowsAudio::new(runner);
+
+        assert_eq!(
+            audio.list_devices().unwrap(),
+            [
+                AudioDevice {
+                    id: "{0.0.0.00000000}.{a1}".into(),
+                    name: "Speakers (Realtek Audio)".into(),
+                    kind: DeviceKind::Output,
+                    is_default: true,
+                    volume: Some(35),
+                    muted: Some(false),
+                },
+                AudioDevice {
+                    id: "{0.0.1.00000000}.{b2}".into(),
+                    name: "Microphone\tArray".into(),
+                    kind: DeviceKind::Input,
+                    is_default: false,
+                    volume: Some(80),
+                    muted: Some(true),
+                },
+            ]
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn windows_quotes_ids_and_scales_levels() {
+        let runner = Arc::new(FakeRunner::default());
+        let audio = WindowsAudio::new(runner.clone());
+
+        audio
+            .set_device_volume(DeviceKind::Input, "it's", 35)
+            .unwrap();
+        assert_eq!(
+            last_command(&runner),
+            "[AstroAudio]::SetLevel(1, 'it''s', 0.35)"
+        );
+        audio
+            .set_device_muted(DeviceKind::Output, "id", true)
+            .unwrap();
+        assert_eq!(
+            last_command(&runner),
+            "[AstroAudio]::SetMute(0, 'id', $true)"
+        );
+        audio.set_default_device(DeviceKind::Output, "id").unwrap();
+        assert_eq!(last_command(&runner), "[AstroAudio]::SetDefault('id')");
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn windows_reads_the_default_output_volume() {
+        let runner = Arc::new(FakeRunner::default());
+        runner.respond(&powershell("[AstroAudio]::State(0, '')"), "35 True");
+        let audio = WindowsAudio::new(runner.clone());
+        assert_eq!(
+            audio.volume().unwrap(),
+            VolumeState {
+                level: 35,
+                muted: true
+            }
+        );
+
+        runner.fail(&powershell("[AstroAudio]::State(0, '')"), "COMException");
+        let error = audio.volume().unwrap_err();
+        assert!(
+            error.contains("Failed to control audio on Windows"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// Display brightness on Linux through the kernel's backlight class.
+//
+// Levels are read from `/sys/class/backlight/<device>` and scaled against its
+// `max_brightness`. Writing the `brightness` file usually needs root, so when
+// that is refused the value goes through logind's `SetBrightness`, which lets
+// the user of the active session change it.
+
+use crate::platformLib::BrightnessControl;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use zbus::blocking::{Connection, Proxy};
+
+const SYSFS_BACKLIGHT: &str = "/sys/class/backlight";
+const LOGIND_SERVICE: &str = "org.freedesktop.login1";
+const LOGIND_SESSION: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+pub struct Backlight {
+    root: PathBuf,
+}
+
+fn read_number(path: &Path) -> Result<u32, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    contents
+        .trim()
+        .parse()
+        .map_err(|_| format!("Unexpected value in {}", path.display()))
+}
+
+// Firmware interfaces know the panel best, raw driver ones the least; the
+// same order systemd uses
+fn type_rank(device: &Path) -> u8 {
+    match fs::read_to_string(device.join("type"))
+        .unwrap_or_default()
+        .trim()
+    {
+        "firmware" => 0,
+        "platform" => 1,
+        "raw" => 2,
+        _ => 3,
+    }
+}
+
+impl Backlight {
+    /// Uses the backlight devices under `root` instead of sysfs.
+    pub fn new(root: PathBuf) -> Self {
+        Backlight { root }
+    }
+
+    pub fn system() -> Self {
+        Self::new(PathBuf::from(SYSFS_BACKLIGHT))
+    }
+
+    /// The preferred backlight device.
+    pub fn device(&self) -> Result<PathBuf, String> {
+        let entries =
+            fs::read_dir(&self.root).map_err(|_| "No backlight device found".to_string())?;
+        let mut devices: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.join("max_brightness").exists())
+            .collect();
+        devices.sort_by_key(|device| (type_rank(device), device.clone()));
+        devices
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No backlight device found".to_string())
+    }
+
+    fn max_brightness(device: &Path) -> Result<u32, String> {
+        match read_number(&device.join("max_brightness"))? {
+            0 => Err("The backlight reports a maximum brightness of 0".into()),
+            max => Ok(max),
+        }
+    }
+
+    // `actual_brightness` is what the hardware reports; `brightness` is the
+    // last requested value
+    fn raw_brightness(device: &Path) -> Result<u32, String> {
+        read_number(&device.join("actual_brightness"))
+            .or_else(|_| read_number(&device.join("brightness")))
+    }
+
+    fn write_raw(device: &Path, value: u32) -> Result<(), String> {
+        match fs::write(device.join("brightness"), value.to_string()) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                let name = device
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .ok_or("Invalid backlight device name")?;
+                set_brightness_through_logind(name, value)
+            }
+            Err(e) => Err(format!("Failed to set the brightness: {}", e)),
+        }
+    }
+}
+
+fn set_brightness_through_logind(device: &str, value: u32) -> Result<(), String> {
+    let conn =
+        Connection::system().map_err(|e| format!("Failed to connect to the system bus: {}", e))?;
+    Proxy::new(&conn, LOGIND_SERVICE, LOGIND_SESSION, SESSION_INTERFACE)
+        .map_err(|e| e.to_string())?
+        .call_method("SetBrightness", &("backlight", device, value))
+        .map(|_| ())
+        .map_err(|e| format!("logind refused to set the brightness: {}", e))
+}
+
+impl BrightnessControl for Backlight {
+    fn brightness(&self) -> Result<u8, String> {
+        let device = self.device()?;
+        let max = Self::max_brightness(&device)? as f64;
+        let raw = Self::raw_brightness(&device)? as f64;
+        Ok((raw * 100.0 / max).round().clamp(0.0, 100.0) as u8)
+    }
+
+    fn set_brightness(&self, percent: u8) -> Result<(), String> {
+        let device = self.device()?;
+        let max = Self::max_brightness(&device)?;
+        let value = (percent.min(100) as f64 * max as f64 / 100.0).round() as u32;
+        Self::write_raw(&device, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A stand-in for /sys/class/backlight, removed when dropped
+    struct FakeSysfs(PathBuf);
+
+    impl FakeSysfs {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("backlight-{}", uuid::Uuid::new_v4()));
+            fs::create_dir(&root).unwrap();
+            FakeSysfs(root)
+        }
+
+        fn device(&self, name: &str, kind: &str, max: u32, brightness: u32) -> PathBuf {
+            let device = self.0.join(name);
+            fs::create_dir(&device).unwrap();
+            fs::write(device.join("type"), format!("{}\n", kind)).unwrap();
+            fs::write(device.join("max_brightness"), format!("{}\n", max)).unwrap();
+            fs::write(device.join("brightness"), format!("{}\n", brightness)).unwrap();
+            device
+        }
+
+        fn backlight(&self) -> Backlight {
+            Backlight::new(self.0.clone())
+        }
+    }
+
+    impl Drop for FakeSysfs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn prefers_firmware_then_platform_then_raw() {
+        let sysfs = FakeSysfs::new();
+        sysfs.device("intel_backlight", "raw", 96000, 0);
+        let platform = sysfs.device("dell_backlight", "platform", 15, 0);
+        let firmware = sysfs.device("acpi_video0", "firmware", 100, 0);
+        // Not a usable device without max_brightness
+        fs::create_dir(sysfs.0.join("acpi_video1")).unwrap();
+        fs::write(sysfs.0.join("acpi_video1/type"), "firmware").unwrap();
+
+        assert_eq!(sysfs.backlight().device().unwrap(), firmware);
+        fs::remove_dir_all(&firmware).unwrap();
+        assert_eq!(sysfs.backlight().device().unwrap(), platform);
+    }
+
+    #[test]
+    fn ranks_unknown_types_last_and_breaks_ties_by_name() {
+        let sysfs = FakeSysfs::new();
+        sysfs.device("mystery", "", 100, 0);
+        let first = sysfs.device("amdgpu_bl0", "raw", 255, 0);
+        sysfs.device("amdgpu_bl1", "raw", 255, 0);
+
+        assert_eq!(sysfs.backlight().device().unwrap(), first);
+    }
+
+    #[test]
+    fn no_devices_is_an_error() {
+        let sysfs = FakeSysfs::new();
+        assert!(sysfs.backlight().device().is_err());
+        assert!(Backlight::new(sysfs.0.join("missing"))
+            .brightness()
+            .is_err());
+    }
+
+    #[test]
+    fn scales_against_max_brightness() {
+        let sysfs = FakeSysfs::new();
+        let device = sysfs.device("intel_backlight", "raw", 19393, 0);
+        // The hardware level wins over the last requested one
+        fs::write(device.join("actual_brightness"), "9697\n").unwrap();
+        assert_eq!(sysfs.backlight().brightness().unwrap(), 50);
+
+        fs::remove_file(device.join("actual_brightness")).unwrap();
+        assert_eq!(sysfs.backlight().brightness().unwrap(), 0);
+
+        sysfs.backlight().set_brightness(75).unwrap();
+        assert_eq!(read_number(&device.join("brightness")).unwrap(), 14545);
+    }
+
+    #[test]
+    fn zero_max_brightness_is_an_error() {
+        let sysfs = FakeSysfs::new();
+        sysfs.device("broken", "raw", 0, 0);
+        assert!(sysfs.backlight().brightness().is_err());
+        assert!(sysfs.backlight().set_brightness(50).is_err());
+    }
+
+    #[test]
+    fn percentages_round_trip() {
+        let sysfs = FakeSysfs::new();
+        sysfs.device("intel_backlight", "raw", 937, 0);
+        let backlight = sysfs.backlight();
+        for percent in 0..=100 {
+            backlight.set_brightness(percent).unwrap();
+            assert_eq!(backlight.brightness().unwrap(), percent);
+        }
+
+        // Requests above 100% are capped at the maximum
+        backlight.set_brightness(150).unwrap();
+        assert_eq!(backlight.brightness().unwrap(), 100);
+    }
+
+    #[test]
+    fn coarse_devices_round_to_the_nearest_step() {
+        let sysfs = FakeSysfs::new();
+        let device = sysfs.device("dell_backlight", "platform", 7, 0);
+        let backlight = sysfs.backlight();
+
+        backlight.set_brightness(50).unwrap();
+        assert_eq!(read_number(&device.join("brightness")).unwrap(), 4);
+        assert_eq!(backlight.brightness().unwrap(), 57);
+    }
+}
@@ -0,0 +1,108 @@
+// External programs are run through `CommandRunner` so a fake can stand in
+// for `pactl` and friends, returning canned output and recording the calls.
+
+use std::process::Command;
+
+#[derive(Clone, Debug, Default)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput, String>;
+
+    /// Runs the command and returns its trimmed stdout, failing on a non-zero exit.
+    fn output(&self, program: &str, args: &[&str]) -> Result<String, String> {
+        let output = self.run(program, args)?;
+        if output.success {
+            Ok(output.stdout.trim().to_string())
+        } else {
+            let stderr = output.stderr.trim();
+            Err(if stderr.is_empty() {
+                format!("{} {} failed", program, args.join(" "))
+            } else {
+                format!("{} failed: {}", program, stderr)
+            })
+        }
+    }
+}
+
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput, String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// Answers commands with canned output and records every call. Commands
+/// without an answer succeed with empty output.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeRunner {
+    outputs: std::sync::Mutex<std::collections::HashMap<String, CommandOutput>>,
+    calls: std::sync::Mutex<Vec<Vec<String>>>,
+}
+
+#[cfg(test)]
+impl FakeRunner {
+    /// `command` is the program and its arguments joined by spaces.
+    pub fn respond(&self, command: &str, stdout: &str) -> &Self {
+        self.answer(
+            command,
+            CommandOutput {
+                success: true,
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            },
+        )
+    }
+
+    pub fn fail(&self, command: &str, stderr: &str) -> &Self {
+        self.answer(
+            command,
+            CommandOutput {
+                success: false,
+                stdout: String::new(),
+                stderr: stderr.to_string(),
+            },
+        )
+    }
+
+    fn answer(&self, command: &str, output: CommandOutput) -> &Self {
+        self.outputs
+            .lock()
+            .unwrap()
+            .insert(command.to_string(), output);
+        self
+    }
+
+    /// Each call as the program followed by its arguments.
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for FakeRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput, String> {
+        let mut call = vec![program.to_string()];
+        call.extend(args.iter().map(|arg| arg.to_string()));
+        let output = self.outputs.lock().unwrap().get(&call.join(" ")).cloned();
+        self.calls.lock().unwrap().push(call);
+        Ok(output.unwrap_or(CommandOutput {
+            success: true,
+            ..Default::default()
+        }))
+    }
+}
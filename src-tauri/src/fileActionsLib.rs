@@ -0,0 +1,532 @@
+use crate::frecencyLib::{self, UsageKind};
+use crate::platformLib;
+use arboard::Clipboard;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct OpenWithApp {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub is_default: bool,
+}
+
+// A broken symlink still counts, so it can be renamed or trashed
+fn existing_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if fs::symlink_metadata(&path).is_ok() {
+        Ok(path)
+    } else {
+        Err(format!("{} does not exist", path.display()))
+    }
+}
+
+// Commands take their arguments as text
+fn path_arg(path: &Path) -> Result<&str, String> {
+    path.to_str()
+        .ok_or_else(|| format!("{} is not a valid UTF-8 path", path.display()))
+}
+
+fn run(program: &str, args: &[&str], action: &str) -> Result<(), String> {
+    platformLib::current()
+        .runner
+        .output(program, args)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to {}: {}", action, e))
+}
+
+// Opens `target` as Explorer would, returning without waiting for the app to exit
+#[cfg(target_os = "windows")]
+fn shell_execute(target: &str, parameters: Option<&str>) -> Result<(), String> {
+    use windows_sys::Win32::UI::Shell::ShellExecuteW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain(Some(0)).collect() };
+    let operation = wide("open");
+    let target = wide(target);
+    let parameters = parameters.map(wide);
+    let result = unsafe {
+        ShellExecuteW(
+            std::ptr::null_mut(),
+            operation.as_ptr(),
+            target.as_ptr(),
+            parameters.as_ref().map_or(std::ptr::null(), |p| p.as_ptr()),
+            std::ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values up to 32 are error codes
+    if result as isize > 32 {
+        Ok(())
+    } else {
+        Err(format!("Failed to open file: error {}", result as isize))
+    }
+}
+
+// Opens `path` with `app`, or with the default application when `app` is omitted
+#[tauri::command]
+pub fn open_file_with(path: &str, app: Option<String>) -> Result<(), String> {
+    let file = existing_path(path)?;
+    let file = path_arg(&file)?;
+    frecencyLib::track(UsageKind::File, path);
+
+    #[cfg(target_os = "macos")]
+    {
+        match &app {
+            Some(app) => run("open", &["-a", app, file], "open file"),
+            None => run("open", &[file], "open file"),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Not through `cmd /C start`, which would run `&` or `|` in a file name
+        match &app {
+            Some(app) => shell_execute(app, Some(&format!("\"{}\"", file))),
+            None => shell_execute(file, None),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // `app` is a desktop entry id, as returned by `list_open_with_apps`
+        match &app {
+            Some(app) => run("gtk-launch", &[app, file], "open file"),
+            None => run("xdg-open", &[file], "open file"),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn reveal_in_file_manager(path: &str) -> Result<(), String> {
+    let file = existing_path(path)?;
+
+    #[cfg(target_os = "macos")]
+    {
+        run("open", &["-R", path_arg(&file)?], "reveal file in Finder")
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // explorer.exe returns a non-zero code even when it succeeds
+        platformLib::current()
+            .runner
+            .run("explorer", &[&format!("/select,{}", path_arg(&file)?)])
+            .map(|_| ())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let uri = format!(
+            "file://{}",
+            crate::trashLib::encode_path(&std::path::absolute(&file).map_err(|e| e.to_string())?)
+        );
+        let shown = run(
+            "dbus-send",
+            &[
+                "--session",
+                "--print-reply",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+                &format!("array:string:{}", uri),
+                "string:",
+            ],
+            "reveal file",
+        );
+
+        // Not every file manager implements FileManager1; open the folder instead
+        shown.or_else(|_| {
+            let parent = file.parent().unwrap_or(Path::new("/"));
+            run("xdg-open", &[path_arg(parent)?], "reveal file")
+        })
+    }
+}
+
+#[tauri::command]
+pub fn copy_file_path(path: &str) -> Result<(), String> {
+    let file = existing_path(path)?;
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard
+        .set_text(file.display().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn copy_file_contents(path: &str) -> Result<(), String> {
+    let file = existing_path(path)?;
+    let contents = fs::read_to_string(&file)
+        .map_err(|e| format!("Cannot copy {} as text: {}", file.display(), e))?;
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard.set_text(contents).map_err(|e| e.to_string())
+}
+
+// Renames within the same directory and returns the new path
+#[tauri::command]
+pub fn rename_file(path: &str, new_name: &str) -> Result<String, String> {
+    let file = existing_path(path)?;
+
+    if new_name.is_empty() || new_name == "." || new_name == ".." {
+        return Err("Invalid file name".into());
+    }
+    if new_name.contains('/') || (cfg!(target_os = "windows") && new_name.contains('\\')) {
+        return Err("File name cannot contain path separators".into());
+    }
+
+    let target = file.with_file_name(new_name);
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(format!("{} already exists", target.display()));
+    }
+
+    fs::rename(&file, &target).map_err(|e| e.to_string())?;
+    Ok(target.display().to_string())
+}
+
+#[tauri::command]
+pub fn move_to_trash(path: &str) -> Result<(), String> {
+    let file = existing_path(path)?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let absolute = std::path::absolute(&file).map_err(|e| e.to_string())?;
+        let script = format!(
+            "tell application \"Finder\" to delete POSIX file \"{}\"",
+            absolute
+                .display()
+                .to_string()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        );
+        run("osascript", &["-e", &script], "move file to trash")
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let method = if file.is_dir() {
+            "DeleteDirectory"
+        } else {
+            "DeleteFile"
+        };
+        let script = format!(
+            "Add-Type -AssemblyName Microsoft.VisualBasic; [Microsoft.VisualBasic.FileIO.FileSystem]::{}('{}', 'OnlyErrorDialogs', 'SendToRecycleBin')",
+            method,
+            file.display().to_string().replace('\'', "''")
+        );
+        run(
+            "powershell",
+            &["-Command", &script],
+            "move file to Recycle Bin",
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        crate::trashLib::trash_file(&file).map(|_| ())
+    }
+}
+
+#[tauri::command]
+pub fn list_open_with_apps(path: &str) -> Result<Vec<OpenWithApp>, String> {
+    let file = existing_path(path)?;
+
+    #[cfg(target_os = "linux")]
+    {
+        let mime = mime_type(&file)?;
+        Ok(mime_associations::apps_for(&mime))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = file;
+        Err("Listing open-with applications is only supported on Linux.".into())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn mime_type(file: &Path) -> Result<String, String> {
+    let runner = platformLib::current().runner.clone();
+    let path = path_arg(file)?;
+    let query = |program: &str, args: &[&str]| -> Option<String> {
+        let mut full = args.to_vec();
+        full.push(path);
+        let mime = runner.output(program, &full).ok()?;
+        mime.contains('/').then_some(mime)
+    };
+
+    query("xdg-mime", &["query", "filetype"])
+        .or_else(|| query("file", &["--brief", "--mime-type"]))
+        .ok_or_else(|| format!("Could not determine the type of {}", file.display()))
+}
+
+// Desktop entry lookup through mimeapps.list and mimeinfo.cache
+#[cfg(target_os = "linux")]
+pub mod mime_associations {
+    use super::OpenWithApp;
+    use crate::xdgLib;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// `applications` directories, most important first.
+    pub fn application_dirs() -> Vec<PathBuf> {
+        xdgLib::data_home()
+            .into_iter()
+            .chain(xdgLib::data_dirs())
+            .map(|dir| dir.join("applications"))
+            .collect()
+    }
+
+    fn mimeapps_lists() -> Vec<PathBuf> {
+        xdgLib::config_home()
+            .into_iter()
+            .chain(xdgLib::config_dirs())
+            .map(|dir| dir.join("mimeapps.list"))
+            .chain(
+                application_dirs()
+                    .into_iter()
+                    .map(|dir| dir.join("mimeapps.list")),
+            )
+            .collect()
+    }
+
+    /// Parses an ini-style file into `section -> key -> value`.
+    pub fn parse_ini(contents: &str) -> HashMap<String, HashMap<String, String>> {
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current = String::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                current = line[1..line.len() - 1].to_string();
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                sections
+                    .entry(current.clone())
+                    .or_default()
+                    .entry(key.trim().to_string())
+                    .or_insert_with(|| value.trim().to_string());
+            }
+        }
+        sections
+    }
+
+    fn ids(value: Option<&String>) -> Vec<String> {
+        value
+            .map(|v| {
+                v.split(';')
+                    .filter(|id| !id.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Resolves a desktop entry id like `org.gnome.gedit.desktop` to its file.
+    pub fn find_desktop_entry(id: &str) -> Option<PathBuf> {
+        application_dirs().into_iter().find_map(|dir| {
+            let direct = dir.join(id);
+            if direct.is_file() {
+                return Some(direct);
+            }
+            // `foo-bar.desktop` may live at `foo/bar.desktop`
+            let nested = dir.join(id.replacen('-', "/", 1));
+            nested.is_file().then_some(nested)
+        })
+    }
+
+    pub fn desktop_entry_name(path: &PathBuf) -> Option<String> {
+        let contents = fs::read_to_string(path).ok()?;
+        let entry = parse_ini(&contents).remove("Desktop Entry")?;
+        if entry.get("Hidden").map(String::as_str) == Some("true") {
+            return None;
+        }
+        entry.get("Name").cloned()
+    }
+
+    pub fn apps_for(mime: &str) -> Vec<OpenWithApp> {
+        let mut default: Option<String> = None;
+        let mut ordered: Vec<String> = Vec::new();
+        let mut removed: Vec<String> = Vec::new();
+
+        for list in mimeapps_lists() {
+            let Ok(contents) = fs::read_to_string(&list) else {
+                continue;
+            };
+            let sections = parse_ini(&contents);
+            let get = |section: &str| sections.get(section).and_then(|s| s.get(mime));
+
+            if default.is_none() {
+                default = ids(get("Default Applications"))
+                    .into_iter()
+                    .find(|id| !removed.contains(id));
+            }
+            ordered.extend(ids(get("Added Associations")));
+            removed.extend(ids(get("Removed Associations")));
+        }
+
+        for dir in application_dirs() {
+            if let Ok(contents) = fs::read_to_string(dir.join("mimeinfo.cache")) {
+                let sections = parse_ini(&contents);
+                ordered.extend(ids(sections.get("MIME Cache").and_then(|s| s.get(mime))));
+            }
+        }
+
+        let mut apps: Vec<OpenWithApp> = Vec::new();
+        for id in default.iter().chain(ordered.iter()) {
+            if removed.contains(id) || apps.iter().any(|app| &app.id == id) {
+                continue;
+            }
+            if let Some(path) = find_desktop_entry(id) {
+                if let Some(name) = desktop_entry_name(&path) {
+                    apps.push(OpenWithApp {
+                        id: id.clone(),
+                        name,
+                        path: path.display().to_string(),
+                        is_default: default.as_ref() == Some(id),
+                    });
+                }
+            }
+        }
+        apps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("file-actions-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write(&self, name: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn renames_within_the_same_folder() {
+        let temp = TempDir::new();
+        let file = temp.write("notes.txt", "notes");
+        temp.write("taken.txt", "taken");
+        let path = file.to_str().unwrap();
+
+        for bad in ["", ".", "..", "sub/notes.txt"] {
+            assert!(
+                rename_file(path, bad).is_err(),
+                "{:?} should be rejected",
+                bad
+            );
+        }
+        let error = rename_file(path, "taken.txt").unwrap_err();
+        assert!(error.contains("already exists"), "{}", error);
+        assert_eq!(
+            fs::read_to_string(temp.0.join("taken.txt")).unwrap(),
+            "taken"
+        );
+
+        let renamed = rename_file(path, "renamed.txt").unwrap();
+        assert_eq!(PathBuf::from(renamed), temp.0.join("renamed.txt"));
+        assert_eq!(
+            fs::read_to_string(temp.0.join("renamed.txt")).unwrap(),
+            "notes"
+        );
+        assert!(rename_file(path, "again.txt").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lists_associated_apps_default_first() {
+        use crate::xdgLib;
+
+        const VARS: [(&str, &str); 4] = [
+            ("XDG_DATA_HOME", "data"),
+            ("XDG_DATA_DIRS", "system"),
+            ("XDG_CONFIG_HOME", "config"),
+            ("XDG_CONFIG_DIRS", "system-config"),
+        ];
+        let _env = xdgLib::ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let temp = TempDir::new();
+        let previous: Vec<_> = VARS
+            .iter()
+            .map(|(var, dir)| {
+                let previous = std::env::var_os(var);
+                std::env::set_var(var, temp.0.join(dir));
+                (var, previous)
+            })
+            .collect();
+
+        let entry = |name: &str, extra: &str| {
+            format!(
+                "[Desktop Entry]\nName={}\nType=Application\n{}",
+                name, extra
+            )
+        };
+        temp.write("data/applications/vim.desktop", &entry("Vim", ""));
+        temp.write("data/applications/gedit.desktop", &entry("Gedit", ""));
+        temp.write("system/applications/kde/kate.desktop", &entry("Kate", ""));
+        temp.write(
+            "system/applications/hidden.desktop",
+            &entry("Hidden", "Hidden=true\n"),
+        );
+        temp.write("system/applications/removed.desktop", &entry("Removed", ""));
+        temp.write(
+            "config/mimeapps.list",
+            "[Default Applications]\ntext/plain=vim.desktop\n\
+             [Added Associations]\ntext/plain=gedit.desktop;\n\
+             [Removed Associations]\ntext/plain=removed.desktop;\n",
+        );
+        temp.write(
+            "system/applications/mimeinfo.cache",
+            "[MIME Cache]\ntext/plain=kde-kate.desktop;hidden.desktop;removed.desktop;gedit.desktop;missing.desktop;\n\
+             image/png=removed.desktop;\n",
+        );
+
+        let apps = mime_associations::apps_for("text/plain");
+        let other = mime_associations::apps_for("image/png");
+        for (var, previous) in previous {
+            match previous {
+                Some(previous) => std::env::set_var(var, previous),
+                None => std::env::remove_var(var),
+            }
+        }
+
+        let summary: Vec<(&str, &str, bool)> = apps
+            .iter()
+            .map(|app| (app.id.as_str(), app.name.as_str(), app.is_default))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("vim.desktop", "Vim", true),
+                ("gedit.desktop", "Gedit", false),
+                ("kde-kate.desktop", "Kate", false),
+            ]
+        );
+        assert!(apps[2]
+            .path
+            .ends_with("system/applications/kde/kate.desktop"));
+        // Removals only apply to the type they are listed for
+        assert_eq!(other.len(), 1);
+    }
+}
@@ -0,0 +1,388 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+const DEFAULT_MAX_LINES: usize = 40;
+// Only this much of a text file is read for the preview
+const TEXT_SAMPLE_BYTES: u64 = 64 * 1024;
+const THUMBNAIL_SIZE: u32 = 256;
+// Decoding very large images for a thumbnail would stall the UI
+const MAX_THUMBNAIL_SOURCE_BYTES: u64 = 50 * 1024 * 1024;
+
+// Leading bytes of the types we preview specially, or that must not be shown
+// as text, whatever the extension says. Those at offset 8 follow "RIFF".
+const SIGNATURES: [(usize, &[u8], &str); 13] = [
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"II*\0", "image/tiff"),
+    (0, b"MM\0*", "image/tiff"),
+    (8, b"WEBP", "image/webp"),
+    (8, b"WAVE", "audio/wav"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"\x7fELF", "application/x-executable"),
+];
+
+#[derive(Serialize)]
+pub struct FilePermissions {
+    readonly: bool,
+    // e.g. "rwxr-xr-x", unix only
+    mode: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TextPreview {
+    encoding: String,
+    lines: Vec<String>,
+    truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct ImagePreview {
+    width: u32,
+    height: u32,
+    // PNG data URL, at most THUMBNAIL_SIZE on each side
+    thumbnail: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct FilePreview {
+    path: String,
+    mime_type: String,
+    is_dir: bool,
+    size: u64,
+    created: Option<String>,
+    modified: Option<String>,
+    accessed: Option<String>,
+    permissions: FilePermissions,
+    text: Option<TextPreview>,
+    image: Option<ImagePreview>,
+    // EXIF tags for images, ID3 frames for audio
+    metadata: BTreeMap<String, String>,
+}
+
+fn format_time(time: std::io::Result<SystemTime>) -> Option<String> {
+    time.ok().map(|t| DateTime::<Local>::from(t).to_rfc3339())
+}
+
+fn permissions(meta: &fs::Metadata) -> FilePermissions {
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        let bits = meta.permissions().mode();
+        let mode: String = (0..9)
+            .map(|i| {
+                let set = bits & (0o400 >> i) != 0;
+                match (set, i % 3) {
+                    (false, _) => '-',
+                    (true, 0) => 'r',
+                    (true, 1) => 'w',
+                    (true, _) => 'x',
+                }
+            })
+            .collect();
+        Some(mode)
+    };
+    #[cfg(not(unix))]
+    let mode = None;
+
+    FilePermissions {
+        readonly: meta.permissions().readonly(),
+        mode,
+    }
+}
+
+fn sniff_mime(path: &Path) -> Option<&'static str> {
+    let mut header = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(16)
+        .read_to_end(&mut header)
+        .ok()?;
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| {
+            (*offset == 0 || header.starts_with(b"RIFF"))
+                && header.get(*offset..offset + magic.len()) == Some(*magic)
+        })
+        .map(|(_, _, mime)| *mime)
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/toml"
+                | "application/x-yaml"
+                | "application/x-sh"
+        )
+}
+
+// Detects the encoding from a BOM, then UTF-8 validity, falling back to Windows-1252
+fn decode_text(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((encoding, bom_len)) = encoding_rs::Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding.name());
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "UTF-8"),
+        // A truncated sample may end mid-character
+        Err(e) if e.error_len().is_none() => (
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned(),
+            "UTF-8",
+        ),
+        Err(_) => {
+            let (text, _, _) = encoding_rs::WINDOWS_1252.decode(bytes);
+            (text.into_owned(), encoding_rs::WINDOWS_1252.name())
+        }
+    }
+}
+
+fn text_preview(path: &Path, mime: &str, max_lines: usize) -> Option<TextPreview> {
+    let mut sample = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(TEXT_SAMPLE_BYTES)
+        .read_to_end(&mut sample)
+        .ok()?;
+
+    // UTF-16 text is full of NULs, so only sniff when there is no BOM
+    let has_bom = encoding_rs::Encoding::for_bom(&sample).is_some();
+    if !is_text_mime(mime) && !has_bom && sample.contains(&0) {
+        return None;
+    }
+
+    let (text, encoding) = decode_text(&sample);
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let truncated =
+        lines.len() > max_lines || fs::metadata(path).is_ok_and(|m| m.len() > TEXT_SAMPLE_BYTES);
+    lines.truncate(max_lines);
+
+    Some(TextPreview {
+        encoding: encoding.to_string(),
+        lines,
+        truncated,
+    })
+}
+
+fn image_preview(path: &Path, size: u64) -> Option<ImagePreview> {
+    let reader = image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?;
+    reader.format()?;
+    let (width, height) = reader.into_dimensions().ok()?;
+
+    let thumbnail = if size <= MAX_THUMBNAIL_SOURCE_BYTES {
+        image::ImageReader::open(path)
+            .ok()
+            .and_then(|r| r.with_guessed_format().ok())
+            .and_then(|r| r.decode().ok())
+            .and_then(|img| {
+                let mut png = Cursor::new(Vec::new());
+                img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+                    .write_to(&mut png, image::ImageFormat::Png)
+                    .ok()?;
+                Some(format!(
+                    "data:image/png;base64,{}",
+                    STANDARD.encode(png.into_inner())
+                ))
+            })
+    } else {
+        None
+    };
+
+    Some(ImagePreview {
+        width,
+        height,
+        thumbnail,
+    })
+}
+
+fn exif_metadata(path: &Path, metadata: &mut BTreeMap<String, String>) {
+    let Ok(file) = fs::File::open(path) else {
+        return;
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+        return;
+    };
+    for field in exif.fields().filter(|f| f.ifd_num == exif::In::PRIMARY) {
+        metadata.insert(
+            field.tag.to_string(),
+            field.display_value().with_unit(&exif).to_string(),
+        );
+    }
+}
+
+fn id3_metadata(path: &Path, metadata: &mut BTreeMap<String, String>) {
+    use id3::TagLike;
+
+    let Ok(tag) = id3::Tag::read_from_path(path) else {
+        return;
+    };
+    let mut insert = |key: &str, value: Option<String>| {
+        if let Some(value) = value {
+            metadata.insert(key.to_string(), value);
+        }
+    };
+    insert("Title", tag.title().map(String::from));
+    insert("Artist", tag.artist().map(String::from));
+    insert("Album", tag.album().map(String::from));
+    insert("AlbumArtist", tag.album_artist().map(String::from));
+    insert("Genre", tag.genre_parsed().map(|g| g.into_owned()));
+    insert("Year", tag.year().map(|y| y.to_string()));
+    insert("Track", tag.track().map(|t| t.to_string()));
+    insert(
+        "Duration",
+        tag.duration().map(|ms| format!("{}s", ms / 1000)),
+    );
+}
+
+#[tauri::command]
+pub fn preview_file(path: &str, max_lines: Option<usize>) -> Result<FilePreview, String> {
+    let file = Path::new(path);
+    let meta = fs::metadata(file).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mime_type = if meta.is_dir() {
+        "inode/directory".to_string()
+    } else {
+        match sniff_mime(file) {
+            Some(mime) => mime.to_string(),
+            None => mime_guess::from_path(file)
+                .first_or_octet_stream()
+                .essence_str()
+                .to_string(),
+        }
+    };
+
+    let mut preview = FilePreview {
+        path: path.to_string(),
+        is_dir: meta.is_dir(),
+        size: meta.len(),
+        created: format_time(meta.created()),
+        modified: format_time(meta.modified()),
+        accessed: format_time(meta.accessed()),
+        permissions: permissions(&meta),
+        text: None,
+        image: None,
+        metadata: BTreeMap::new(),
+        mime_type,
+    };
+    if meta.is_dir() {
+        return Ok(preview);
+    }
+
+    if preview.mime_type.starts_with("image/") {
+        preview.image = image_preview(file, meta.len());
+        exif_metadata(file, &mut preview.metadata);
+    } else if preview.mime_type.starts_with("audio/") {
+        id3_metadata(file, &mut preview.metadata);
+    } else {
+        preview.text = text_preview(
+            file,
+            &preview.mime_type,
+            max_lines.unwrap_or(DEFAULT_MAX_LINES),
+        );
+    }
+
+    Ok(preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("file-preview-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write(&self, name: &str, contents: &[u8]) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn detects_text_encodings() {
+        assert_eq!(decode_text("héllo".as_bytes()), ("héllo".into(), "UTF-8"));
+        assert_eq!(decode_text(b"\xef\xbb\xbfbom"), ("bom".into(), "UTF-8"));
+        assert_eq!(decode_text(b"\xff\xfeh\0i\0"), ("hi".into(), "UTF-16LE"));
+        assert_eq!(decode_text(b"\xfe\xff\0h\0i"), ("hi".into(), "UTF-16BE"));
+        // A sample cut in the middle of "é" drops the partial character
+        assert_eq!(decode_text(b"caf\xc3"), ("caf".into(), "UTF-8"));
+        assert_eq!(
+            decode_text(b"\x93quoted\x94 caf\xe9"),
+            ("\u{201c}quoted\u{201d} café".into(), "windows-1252")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn formats_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new();
+        let file = temp.write("script.sh", b"#!/bin/sh\n");
+        for (bits, mode, readonly) in [
+            (0o754, "rwxr-xr--", false),
+            (0o640, "rw-r-----", false),
+            (0o444, "r--r--r--", true),
+        ] {
+            fs::set_permissions(&file, fs::Permissions::from_mode(bits)).unwrap();
+            let permissions = permissions(&fs::metadata(&file).unwrap());
+            assert_eq!(permissions.mode.as_deref(), Some(mode));
+            assert_eq!(permissions.readonly, readonly);
+        }
+    }
+
+    #[test]
+    fn sniffs_types_before_trusting_the_extension() {
+        let temp = TempDir::new();
+        let mime = |name: &str, contents: &[u8]| {
+            let path = temp.write(name, contents);
+            preview_file(path.to_str().unwrap(), None)
+                .unwrap()
+                .mime_type
+        };
+
+        assert_eq!(
+            mime("photo.txt", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            "image/png"
+        );
+        assert_eq!(mime("song", b"ID3\x04\0\0\0\0\0\0"), "audio/mpeg");
+        assert_eq!(mime("clip.bin", b"RIFF\x24\0\0\0WAVEfmt "), "audio/wav");
+        assert_eq!(mime("notes.md", b"RIFF is not WAVE"), "text/markdown");
+        assert_eq!(mime("notes.txt", b"WEBP at eight"), "text/plain");
+
+        let text =
+            preview_file(temp.write("README", b"plain\ntext").to_str().unwrap(), None).unwrap();
+        assert_eq!(text.mime_type, "application/octet-stream");
+        assert_eq!(text.text.unwrap().lines, ["plain", "text"]);
+    }
+}
@@ -0,0 +1,277 @@
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// A selection loses half its weight every three days
+const HALF_LIFE_SECS: f64 = 3.0 * 24.0 * 60.0 * 60.0;
+// How many fuzzy-score points a frecency of 1.0 is worth
+const RANK_WEIGHT: f64 = 20.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageKind {
+    App,
+    File,
+    Snippet,
+    Command,
+    Link,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UsageEntry {
+    pub count: u64,
+    pub last_used: i64,
+    // Decayed score as of `last_used`
+    score: f64,
+}
+
+// What one use at `used_at` is worth at `now`
+fn decay(used_at: i64, now: i64) -> f64 {
+    let age = (now - used_at).max(0) as f64;
+    0.5f64.powf(age / HALF_LIFE_SECS)
+}
+
+impl UsageEntry {
+    fn frecency_at(&self, now: i64) -> f64 {
+        self.score * decay(self.last_used, now)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct UsageStore {
+    entries: HashMap<UsageKind, HashMap<String, UsageEntry>>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl UsageStore {
+    fn load(path: PathBuf) -> Self {
+        let mut store: UsageStore = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        store.path = Some(path);
+        store
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    fn record_at(&mut self, kind: UsageKind, key: &str, now: i64) {
+        let entry = self
+            .entries
+            .entry(kind)
+            .or_default()
+            .entry(key.to_string())
+            .or_insert(UsageEntry {
+                count: 0,
+                last_used: now,
+                score: 0.0,
+            });
+        entry.score = entry.frecency_at(now) + 1.0;
+        entry.count += 1;
+        entry.last_used = now;
+    }
+
+    fn frecency_at(&self, kind: UsageKind, key: &str, now: i64) -> f64 {
+        self.entries
+            .get(&kind)
+            .and_then(|entries| entries.get(key))
+            .map_or(0.0, |entry| entry.frecency_at(now))
+    }
+
+    fn sort_at<T, F>(&self, kind: UsageKind, items: Vec<T>, key: F, now: i64) -> Vec<T>
+    where
+        F: Fn(&T) -> &str,
+    {
+        let mut scored: Vec<(f64, T)> = items
+            .into_iter()
+            .map(|item| (self.frecency_at(kind, key(&item), now), item))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+fn boost(frecency: f64) -> i64 {
+    (frecency.ln_1p() * RANK_WEIGHT).round() as i64
+}
+
+static USAGE_STORE: Lazy<Mutex<UsageStore>> = Lazy::new(|| Mutex::new(UsageStore::default()));
+
+// Load persisted stats; called once from setup with the app data dir
+pub fn init(data_dir: PathBuf) {
+    let mut store = USAGE_STORE.lock().unwrap();
+    *store = UsageStore::load(data_dir.join("usage.json"));
+}
+
+pub fn record(kind: UsageKind, key: &str) -> Result<(), String> {
+    let mut store = USAGE_STORE.lock().unwrap();
+    store.record_at(kind, key, Utc::now().timestamp());
+    store.save()
+}
+
+/// Records a selection made while doing something else, where failing to
+/// save the stats shouldn't fail the action itself.
+pub fn track(kind: UsageKind, key: &str) {
+    if let Err(e) = record(kind, key) {
+        eprintln!("Failed to save usage stats: {}", e);
+    }
+}
+
+pub fn frecency(kind: UsageKind, key: &str) -> f64 {
+    USAGE_STORE
+        .lock()
+        .unwrap()
+        .frecency_at(kind, key, Utc::now().timestamp())
+}
+
+/// Ranking points to add on top of a fuzzy score for `key`.
+///
+/// Logarithmic so a handful of recent picks reorders close matches without
+/// letting a heavily used item outrank a much better textual match.
+pub fn rank_boost(kind: UsageKind, key: &str) -> i64 {
+    boost(frecency(kind, key))
+}
+
+/// Ranking points for a single use at `used_at` (Unix seconds) that happened
+/// outside the app, such as a document opened in another program.
+pub fn recency_boost(used_at: i64) -> i64 {
+    boost(decay(used_at, Utc::now().timestamp()))
+}
+
+/// Stable-sorts `items` by descending frecency, for listings without a query.
+pub fn sort_by_frecency<T, F>(kind: UsageKind, items: Vec<T>, key: F) -> Vec<T>
+where
+    F: Fn(&T) -> &str,
+{
+    USAGE_STORE
+        .lock()
+        .unwrap()
+        .sort_at(kind, items, key, Utc::now().timestamp())
+}
+
+#[tauri::command]
+pub fn record_usage(kind: UsageKind, key: &str) -> Result<(), String> {
+    record(kind, key)
+}
+
+#[tauri::command]
+pub fn get_frecency_scores(kind: UsageKind) -> HashMap<String, f64> {
+    let store = USAGE_STORE.lock().unwrap();
+    let now = Utc::now().timestamp();
+    store
+        .entries
+        .get(&kind)
+        .map(|entries| {
+            entries
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.frecency_at(now)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Clears the stats for one kind, or everything when `kind` is omitted
+#[tauri::command]
+pub fn reset_usage_stats(kind: Option<UsageKind>) -> Result<(), String> {
+    let mut store = USAGE_STORE.lock().unwrap();
+    match kind {
+        Some(kind) => {
+            store.entries.remove(&kind);
+        }
+        None => store.entries.clear(),
+    }
+    store.save()
+}
+
+#[tauri::command]
+pub fn export_usage_stats() -> Result<String, String> {
+    let store = USAGE_STORE.lock().unwrap();
+    serde_json::to_string_pretty(&*store).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const HALF_LIFE: i64 = 3 * DAY;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn halves_every_half_life() {
+        let mut store = UsageStore::default();
+        store.record_at(UsageKind::App, "Firefox", 0);
+
+        let at = |now| store.frecency_at(UsageKind::App, "Firefox", now);
+        assert!(close(at(0), 1.0));
+        assert!(close(at(HALF_LIFE), 0.5));
+        assert!(close(at(2 * HALF_LIFE), 0.25));
+        // Clock skew never inflates a score
+        assert!(close(at(-DAY), 1.0));
+        assert_eq!(store.frecency_at(UsageKind::File, "Firefox", 0), 0.0);
+    }
+
+    #[test]
+    fn repeated_records_add_to_the_decayed_score() {
+        let mut store = UsageStore::default();
+        store.record_at(UsageKind::Command, "Translate", 0);
+        store.record_at(UsageKind::Command, "Translate", 0);
+        assert!(close(
+            store.frecency_at(UsageKind::Command, "Translate", 0),
+            2.0
+        ));
+
+        store.record_at(UsageKind::Command, "Translate", HALF_LIFE);
+        let entry = &store.entries[&UsageKind::Command]["Translate"];
+        assert_eq!(entry.count, 3);
+        assert_eq!(entry.last_used, HALF_LIFE);
+        assert!(close(
+            store.frecency_at(UsageKind::Command, "Translate", HALF_LIFE),
+            2.0
+        ));
+    }
+
+    #[test]
+    fn recent_use_outranks_old_frequent_use() {
+        let mut store = UsageStore::default();
+        for _ in 0..4 {
+            store.record_at(UsageKind::File, "old.txt", 0);
+        }
+        store.record_at(UsageKind::File, "new.txt", 10 * DAY);
+        store.record_at(UsageKind::File, "other.txt", 5 * DAY);
+        // Unused items keep their order, after every used one
+
+        let items = vec!["unused.txt", "old.txt", "other.txt", "never.txt", "new.txt"];
+        assert_eq!(
+            store.sort_at(UsageKind::File, items, |item| item, 10 * DAY),
+            vec!["new.txt", "old.txt", "other.txt", "unused.txt", "never.txt"]
+        );
+    }
+
+    #[test]
+    fn boost_grows_slower_than_use() {
+        assert_eq!(boost(0.0), 0);
+        assert_eq!(boost(1.0), 14);
+        assert_eq!(boost(decay(0, HALF_LIFE)), 8);
+        assert_eq!(boost(decay(DAY, 0)), 14);
+        assert!(boost(10.0) < 10 * boost(1.0));
+        assert!(boost(100.0) > boost(10.0));
+    }
+}
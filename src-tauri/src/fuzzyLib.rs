@@ -0,0 +1,301 @@
+use serde::Serialize;
+
+// Scoring constants, loosely modelled after fzf's scheme
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_PATH_SEPARATOR: i64 = 9;
+const BONUS_CAMEL_CASE: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+const BONUS_FILENAME: i64 = 24;
+
+const NO_MATCH: i64 = i64::MIN / 2;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    // Char (not byte) offsets into the candidate, for highlighting
+    pub indices: Vec<usize>,
+}
+
+fn is_path_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+fn is_word_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '_' | '-' | '.' | ',' | ':' | ';' | '(' | ')' | '[' | ']')
+}
+
+fn char_bonus(prev: Option<char>, current: char) -> i64 {
+    match prev {
+        None => BONUS_BOUNDARY,
+        Some(p) if is_path_separator(p) => BONUS_PATH_SEPARATOR,
+        Some(p) if is_word_separator(p) => BONUS_BOUNDARY,
+        Some(p) if p.is_lowercase() && current.is_uppercase() => BONUS_CAMEL_CASE,
+        Some(p) if !p.is_ascii_digit() && current.is_ascii_digit() => BONUS_CAMEL_CASE,
+        _ => 0,
+    }
+}
+
+fn chars_equal(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Case-insensitive subsequence match of `query` against `candidate`.
+///
+/// Returns `None` when the query is not a subsequence of the candidate,
+/// otherwise the best-scoring alignment and the matched char indices.
+/// Word boundaries, camelCase humps and path separators earn bonuses so
+/// that acronyms like "vsc" rank "Visual Studio Code" highly.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+    if query.len() > candidate.len() {
+        return None;
+    }
+
+    // Cheap rejection before running the full alignment
+    let mut qi = 0;
+    for &c in &candidate {
+        if qi < query.len() && chars_equal(query[qi], c) {
+            qi += 1;
+        }
+    }
+    if qi < query.len() {
+        return None;
+    }
+
+    let m = query.len();
+    let n = candidate.len();
+    let bonus: Vec<i64> = (0..n)
+        .map(|j| {
+            char_bonus(
+                if j == 0 { None } else { Some(candidate[j - 1]) },
+                candidate[j],
+            )
+        })
+        .collect();
+
+    // scores[i][j]: best score with query[i] matched at candidate[j]
+    // from[i][j]: candidate position of query[i - 1] in that alignment
+    let mut scores = vec![vec![NO_MATCH; n]; m];
+    let mut from = vec![vec![usize::MAX; n]; m];
+
+    for j in 0..n {
+        if chars_equal(query[0], candidate[j]) {
+            scores[0][j] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+        }
+    }
+
+    for i in 1..m {
+        // Best gapped predecessor (k <= j - 2) carried along with affine gap costs
+        let mut gapped = NO_MATCH;
+        let mut gapped_from = usize::MAX;
+
+        for j in i..n {
+            if j >= 2 {
+                let opened = scores[i - 1][j - 2] + SCORE_GAP_START;
+                let extended = gapped + SCORE_GAP_EXTENSION;
+                if opened >= extended {
+                    gapped = opened;
+                    gapped_from = j - 2;
+                } else {
+                    gapped = extended;
+                }
+            }
+
+            if !chars_equal(query[i], candidate[j]) {
+                continue;
+            }
+
+            let consecutive = scores[i - 1][j - 1];
+            let consecutive = if consecutive > NO_MATCH {
+                consecutive + BONUS_CONSECUTIVE.max(bonus[j])
+            } else {
+                NO_MATCH
+            };
+            let gapped_score = if gapped > NO_MATCH {
+                gapped + bonus[j]
+            } else {
+                NO_MATCH
+            };
+
+            let (best, prev) = if consecutive >= gapped_score {
+                (consecutive, j - 1)
+            } else {
+                (gapped_score, gapped_from)
+            };
+            if best > NO_MATCH {
+                scores[i][j] = best + SCORE_MATCH;
+                from[i][j] = prev;
+            }
+        }
+    }
+
+    let (mut end, score) = scores[m - 1]
+        .iter()
+        .enumerate()
+        .max_by_key(|(j, s)| (**s, std::cmp::Reverse(*j)))
+        .map(|(j, s)| (j, *s))?;
+    if score <= NO_MATCH {
+        return None;
+    }
+
+    let mut indices = vec![0; m];
+    for i in (0..m).rev() {
+        indices[i] = end;
+        end = from[i][end];
+    }
+
+    Some(FuzzyMatch { score, indices })
+}
+
+/// Like [`fuzzy_match`], but aware of path structure.
+///
+/// Queries without a separator are matched against the file name first and
+/// rewarded for it, falling back to the whole path. Queries containing a
+/// separator are matched against the whole path so "src/main" works.
+pub fn fuzzy_match_path(query: &str, path: &str) -> Option<FuzzyMatch> {
+    if query.chars().any(is_path_separator) {
+        return fuzzy_match(query, path);
+    }
+
+    let name_start = path
+        .char_indices()
+        .rfind(|(_, c)| is_path_separator(*c))
+        .map(|(i, _)| path[..i].chars().count() + 1)
+        .unwrap_or(0);
+    let name: String = path.chars().skip(name_start).collect();
+
+    if let Some(mut m) = fuzzy_match(query, &name) {
+        m.score += BONUS_FILENAME;
+        for index in m.indices.iter_mut() {
+            *index += name_start;
+        }
+        return Some(m);
+    }
+
+    fuzzy_match(query, path)
+}
+
+/// Filters and sorts `items` by fuzzy score, best first.
+///
+/// Ties are broken by the shorter candidate, then by original order.
+pub fn rank_by<T, F>(query: &str, items: Vec<T>, key: F) -> Vec<(T, FuzzyMatch)>
+where
+    F: Fn(&T) -> &str,
+{
+    let mut ranked: Vec<(T, FuzzyMatch, usize)> = items
+        .into_iter()
+        .filter_map(|item| {
+            let text = key(&item);
+            let len = text.chars().count();
+            fuzzy_match(query, text).map(|m| (item, m, len))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(a.2.cmp(&b.2)));
+    ranked.into_iter().map(|(item, m, _)| (item, m)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequences_case_insensitively() {
+        assert!(fuzzy_match("SAF", "Safari").is_some());
+        assert!(fuzzy_match("sfr", "Safari").is_some());
+        assert!(fuzzy_match("xyz", "Safari").is_none());
+        assert!(fuzzy_match("safarii", "Safari").is_none());
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        let m = fuzzy_match("", "Safari").unwrap();
+        assert_eq!(m.score, 0);
+        assert!(m.indices.is_empty());
+    }
+
+    #[test]
+    fn acronym_hits_word_starts() {
+        let m = fuzzy_match("vsc", "Visual Studio Code").unwrap();
+        assert_eq!(m.indices, vec![0, 7, 14]);
+    }
+
+    #[test]
+    fn acronym_beats_scattered_match() {
+        let acronym = fuzzy_match("vsc", "Visual Studio Code").unwrap();
+        let scattered = fuzzy_match("vsc", "obvious tactics").unwrap();
+        assert!(acronym.score > scattered.score);
+    }
+
+    #[test]
+    fn camel_case_humps_earn_a_bonus() {
+        let m = fuzzy_match("sfl", "searchFilesLib.rs").unwrap();
+        assert_eq!(m.indices, vec![0, 6, 11]);
+
+        let humps = fuzzy_match("fl", "searchFilesLib").unwrap();
+        let inside = fuzzy_match("fl", "selfless").unwrap();
+        assert!(humps.score > inside.score);
+    }
+
+    #[test]
+    fn consecutive_matches_beat_gaps() {
+        let together = fuzzy_match("main", "main.rs").unwrap();
+        let apart = fuzzy_match("main", "my_animation").unwrap();
+        assert!(together.score > apart.score);
+        assert_eq!(together.indices, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn indices_count_chars_not_bytes() {
+        let m = fuzzy_match("rsm", "Café Résumé").unwrap();
+        assert_eq!(m.indices, vec![5, 7, 9]);
+    }
+
+    #[test]
+    fn path_query_prefers_the_file_name() {
+        let path = "/Users/me/projects/app/src/main.rs";
+        let m = fuzzy_match_path("main", path).unwrap();
+        let name_start = path.rfind('/').unwrap() + 1;
+        assert_eq!(m.indices, (name_start..name_start + 4).collect::<Vec<_>>());
+
+        let in_name = fuzzy_match_path("app", "/Users/me/Applications/app.txt").unwrap();
+        let in_dir = fuzzy_match_path("app", "/Users/me/Applications/notes.txt").unwrap();
+        assert!(in_name.score > in_dir.score);
+    }
+
+    #[test]
+    fn path_query_falls_back_to_the_directories() {
+        let path = "/Users/me/projects/notes.txt";
+        let m = fuzzy_match_path("proj", path).unwrap();
+        assert_eq!(m.indices, vec![10, 11, 12, 13]);
+    }
+
+    #[test]
+    fn separator_in_query_matches_the_whole_path() {
+        let m = fuzzy_match_path("src/main", "/app/src/main.rs").unwrap();
+        assert_eq!(m.indices, (5..13).collect::<Vec<_>>());
+        assert!(fuzzy_match_path("src/main", "/app/lib/main.rs").is_none());
+    }
+
+    #[test]
+    fn rank_by_orders_best_first() {
+        let items = vec!["obvious tactics", "Visual Studio Code", "Safari"];
+        let ranked: Vec<&str> = rank_by("vsc", items, |s| s)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        assert_eq!(ranked, vec!["Visual Studio Code", "obvious tactics"]);
+    }
+}
@@ -0,0 +1,241 @@
+// Power and session control on Linux through systemd-logind's D-Bus API,
+// which checks with polkit and so works without root for the active user.
+//
+// `Logind::new` takes any connection, so a private bus with a stand-in
+// `org.freedesktop.login1` can replace the system one.
+
+use crate::platformLib::PowerControl;
+use once_cell::sync::OnceCell;
+use zbus::blocking::{Connection, Proxy};
+
+const SERVICE: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+// logind resolves this to the caller's session, or the user's graphical one
+const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+pub struct Logind {
+    conn: OnceCell<Connection>,
+}
+
+impl Logind {
+    #[cfg(test)]
+    pub fn new(conn: Connection) -> Self {
+        Logind {
+            conn: OnceCell::with_value(conn),
+        }
+    }
+
+    /// Connects to the system bus on first use.
+    pub fn system() -> Self {
+        Logind {
+            conn: OnceCell::new(),
+        }
+    }
+
+    fn conn(&self) -> Result<&Connection, String> {
+        self.conn.get_or_try_init(|| {
+            Connection::system().map_err(|e| format!("Failed to connect to the system bus: {}", e))
+        })
+    }
+
+    fn manager(&self) -> Result<Proxy<'_>, String> {
+        Proxy::new(self.conn()?, SERVICE, MANAGER_PATH, MANAGER_INTERFACE)
+            .map_err(|e| e.to_string())
+    }
+
+    fn session(&self) -> Result<Proxy<'_>, String> {
+        Proxy::new(self.conn()?, SERVICE, SESSION_PATH, SESSION_INTERFACE)
+            .map_err(|e| e.to_string())
+    }
+
+    // Runs a `Can*` check first so an unsupported action reports why
+    fn power_action(&self, action: &str, description: &str) -> Result<(), String> {
+        let manager = self.manager()?;
+        let answer: String = manager
+            .call(format!("Can{}", action).as_str(), &())
+            .map_err(|e| format!("Failed to ask logind about {}: {}", description, e))?;
+        match answer.as_str() {
+            "na" => return Err(format!("This system can't {}", description)),
+            "no" => return Err(format!("You aren't allowed to {}", description)),
+            // "yes", or "challenge" when polkit will ask for a password
+            _ => {}
+        }
+        // `true` lets polkit prompt for authentication
+        manager
+            .call_method(action, &(true,))
+            .map(|_| ())
+            .map_err(|e| format!("Failed to {}: {}", description, e))
+    }
+
+    /// The id of the session this app runs in.
+    pub fn session_id(&self) -> Result<String, String> {
+        self.session()?
+            .get_property("Id")
+            .map_err(|e| format!("Failed to find the current session: {}", e))
+    }
+}
+
+impl PowerControl for Logind {
+    fn shutdown(&self) -> Result<(), String> {
+        self.power_action("PowerOff", "shut down")
+    }
+
+    fn restart(&self) -> Result<(), String> {
+        self.power_action("Reboot", "restart")
+    }
+
+    // Only this session; the manager's `LockSessions` locks every user's
+    fn lock(&self) -> Result<(), String> {
+        self.session()?
+            .call_method("Lock", &())
+            .map(|_| ())
+            .map_err(|e| format!("Failed to lock the screen: {}", e))
+    }
+
+    fn suspend(&self) -> Result<(), String> {
+        self.power_action("Suspend", "suspend")
+    }
+
+    fn hibernate(&self) -> Result<(), String> {
+        self.power_action("Hibernate", "hibernate")
+    }
+
+    fn log_out(&self) -> Result<(), String> {
+        let id = self.session_id()?;
+        self.manager()?
+            .call_method("TerminateSession", &(id,))
+            .map(|_| ())
+            .map_err(|e| format!("Failed to log out: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbusTestLib::PrivateBus;
+    use std::sync::{Arc, Mutex};
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    struct StandInManager {
+        calls: Calls,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl StandInManager {
+        fn can_power_off(&self) -> &str {
+            "yes"
+        }
+
+        fn can_reboot(&self) -> &str {
+            "challenge"
+        }
+
+        fn can_suspend(&self) -> &str {
+            "no"
+        }
+
+        fn can_hibernate(&self) -> &str {
+            "na"
+        }
+
+        fn power_off(&self, interactive: bool) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("PowerOff {}", interactive));
+        }
+
+        fn reboot(&self, interactive: bool) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("Reboot {}", interactive));
+        }
+
+        fn suspend(&self, interactive: bool) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("Suspend {}", interactive));
+        }
+
+        fn terminate_session(&self, id: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("TerminateSession {}", id));
+        }
+    }
+
+    struct StandInSession {
+        calls: Calls,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Session")]
+    impl StandInSession {
+        fn lock(&self) {
+            self.calls.lock().unwrap().push("Lock".into());
+        }
+
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "c2".into()
+        }
+    }
+
+    #[test]
+    fn drives_a_stand_in_logind_on_a_private_bus() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed; skipping");
+            return;
+        };
+        let calls = Calls::default();
+        let _logind = bus
+            .builder()
+            .name(SERVICE)
+            .unwrap()
+            .serve_at(
+                MANAGER_PATH,
+                StandInManager {
+                    calls: calls.clone(),
+                },
+            )
+            .unwrap()
+            .serve_at(
+                SESSION_PATH,
+                StandInSession {
+                    calls: calls.clone(),
+                },
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let logind = Logind::new(bus.connect());
+
+        assert_eq!(logind.session_id().unwrap(), "c2");
+        logind.shutdown().unwrap();
+        logind.restart().unwrap();
+        logind.lock().unwrap();
+        logind.log_out().unwrap();
+        assert_eq!(
+            logind.suspend().unwrap_err(),
+            "You aren't allowed to suspend"
+        );
+        assert_eq!(
+            logind.hibernate().unwrap_err(),
+            "This system can't hibernate"
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "PowerOff true".to_string(),
+                "Reboot true".to_string(),
+                "Lock".to_string(),
+                "TerminateSession c2".to_string(),
+            ]
+        );
+    }
+}
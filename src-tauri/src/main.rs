@@ -7,35 +7,52 @@ use arboard::Clipboard;
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::{thread, time::Duration};
+use tauri::Manager;
 use tauri_plugin_notification::NotificationExt;
 
 mod appsLib;
+mod audioDevicesLib;
+#[cfg(target_os = "linux")]
+mod backlightLib;
 mod clockLib;
+mod commandLib;
+#[cfg(all(test, target_os = "linux"))]
+mod dbusTestLib;
+mod fileActionsLib;
+mod filePreviewLib;
+mod frecencyLib;
+mod fuzzyLib;
 mod liveDataLib;
+#[cfg(target_os = "linux")]
+mod logindLib;
 mod mediaLib;
+mod mediaTypesLib;
+#[cfg(target_os = "linux")]
+mod mprisLib;
+mod placeholdersLib;
+mod platformLib;
+#[cfg(target_os = "linux")]
+mod pulseAudioLib;
+mod recentFilesLib;
 mod searchFilesLib;
 mod settings;
+mod snippetImportLib;
+mod snippetSyncLib;
 mod snippetsLib;
 mod systemManagementLib;
+mod textExpansionLib;
+#[cfg(target_os = "linux")]
+mod trashLib;
 mod windowMngLib;
+#[cfg(target_os = "linux")]
+mod xdgLib;
+#[cfg(all(test, target_os = "linux"))]
+mod xvfbTestLib;
 
 #[tauri::command]
 fn open_link(url: &str) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(url)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("cmd")
-            .args(&["/C", "start", "", url])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    Ok(())
+    frecencyLib::track(frecencyLib::UsageKind::Link, url);
+    platformLib::current().apps.open_url(url)
 }
 
 // Clipboard history (text only)
@@ -99,7 +116,6 @@ fn calculate_expression(expression: &str) -> Result<f64, String> {
 
 #[tauri::command]
 fn search_web(query: &str) -> Result<(), String> {
-    use std::process::Command;
     use urlencoding::encode;
 
     let url = if query.starts_with("http") {
@@ -107,26 +123,11 @@ fn search_web(query: &str) -> Result<(), String> {
     } else {
         format!("https://www.google.com/search?q={}", encode(query))
     };
-
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("open")
-            .arg(&url)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("cmd")
-            .args(&["/C", "start", "", &url])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    Ok(())
+    platformLib::current().apps.open_url(&url)
 }
 
 #[derive(Serialize)]
-struct AppInfo {
+pub struct AppInfo {
     name: String,
     path: String,
 }
@@ -249,28 +250,86 @@ fn main() {
             search_web,
             appsLib::launch_app,
             appsLib::list_apps,
+            appsLib::search_apps,
             windowMngLib::minimize_window,
             windowMngLib::maximize_window,
             windowMngLib::resize_window_80,
             windowMngLib::close_window,
             windowMngLib::close_window_command,
+            windowMngLib::paste_into_previous_app,
+            systemManagementLib::get_brightness,
             systemManagementLib::set_brightness,
             systemManagementLib::increase_brightness,
             systemManagementLib::decrease_brightness,
             systemManagementLib::restart_system,
             systemManagementLib::shutdown_system,
             systemManagementLib::lock_system,
+            systemManagementLib::suspend_system,
+            systemManagementLib::hibernate_system,
+            systemManagementLib::logout_system,
+            systemManagementLib::schedule_power_action,
+            systemManagementLib::get_pending_action,
+            systemManagementLib::cancel_pending_action,
             systemManagementLib::empty_trash,
+            systemManagementLib::list_trash,
+            systemManagementLib::restore_from_trash,
+            systemManagementLib::trash_size,
+            mediaLib::get_volume,
             mediaLib::set_volume,
             mediaLib::mute_volume,
             mediaLib::increase_volume,
             mediaLib::decrease_volume,
+            mediaLib::get_microphone_state,
+            mediaLib::toggle_microphone_mute,
+            audioDevicesLib::list_audio_devices,
+            audioDevicesLib::set_default_output,
+            audioDevicesLib::set_default_input,
+            audioDevicesLib::set_device_volume,
+            audioDevicesLib::set_device_mute,
             mediaLib::media_play,
             mediaLib::media_pause,
             mediaLib::media_skip,
             mediaLib::media_previous,
+            mediaLib::media_toggle,
+            mediaLib::media_seek,
+            mediaLib::media_set_position,
+            mediaLib::media_set_shuffle,
+            mediaLib::media_set_loop,
+            mediaLib::media_now_playing,
+            mediaLib::media_list_players,
+            mediaLib::media_select_player,
             searchFilesLib::search_files,
+            fileActionsLib::open_file_with,
+            fileActionsLib::reveal_in_file_manager,
+            fileActionsLib::copy_file_path,
+            fileActionsLib::copy_file_contents,
+            fileActionsLib::rename_file,
+            fileActionsLib::move_to_trash,
+            fileActionsLib::list_open_with_apps,
+            filePreviewLib::preview_file,
+            recentFilesLib::list_recent_files,
             snippetsLib::get_snippets,
+            snippetsLib::search_snippets,
+            snippetsLib::get_snippet_tags,
+            snippetsLib::get_snippet_categories,
+            snippetsLib::create_snippet,
+            snippetsLib::update_snippet,
+            snippetsLib::delete_snippet,
+            snippetsLib::rename_snippet,
+            snippetImportLib::import_snippets,
+            snippetImportLib::export_snippets,
+            snippetSyncLib::get_snippet_sync_settings,
+            snippetSyncLib::configure_snippet_sync,
+            snippetSyncLib::sync_snippets,
+            snippetsLib::get_snippet_arguments,
+            snippetsLib::expand_snippet,
+            textExpansionLib::get_text_expansion_settings,
+            textExpansionLib::set_text_expansion_enabled,
+            textExpansionLib::set_text_expansion_exclusions,
+            frecencyLib::record_usage,
+            frecencyLib::get_frecency_scores,
+            frecencyLib::reset_usage_stats,
+            frecencyLib::export_usage_stats,
             record_clipboard,
             get_clipboard_history,
             clear_clipboard_history,
@@ -284,6 +343,18 @@ fn main() {
             run_macos_shortcut,
         ])
         .setup(move |app| {
+            platformLib::init();
+            if let Ok(data_dir) = app.path().app_data_dir() {
+                frecencyLib::init(data_dir.clone());
+                snippetsLib::init(data_dir.clone());
+                snippetSyncLib::init(app.handle().clone(), data_dir.clone());
+                textExpansionLib::init(data_dir);
+                if let Err(e) = snippetsLib::watch(app.handle().clone()) {
+                    eprintln!("Failed to watch snippets: {}", e);
+                }
+            }
+            mediaLib::watch(app.handle().clone());
+
             app.notification()
                 .builder()
                 .title("AstroLaunch On")
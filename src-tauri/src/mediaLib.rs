@@ -1,71 +1,341 @@
-use std::process::Command;
+use crate::audioDevicesLib::{self, DeviceKind};
+#[cfg(not(target_os = "linux"))]
+use crate::mediaTypesLib::PlaybackStatus;
+use crate::mediaTypesLib::{LoopMode, MediaPlayer, NowPlaying};
+#[cfg(target_os = "linux")]
+use crate::mprisLib;
+use crate::platformLib::{self, VolumeControl};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+#[cfg(not(target_os = "linux"))]
+use std::time::Duration;
+use tauri::Emitter;
+#[cfg(not(target_os = "linux"))]
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+// How often `watch` checks for track and player changes while the launcher
+// is visible, where players can't be listened to
+#[cfg(not(target_os = "linux"))]
+const NOW_PLAYING_POLL: Duration = Duration::from_secs(2);
+
+#[cfg(target_os = "macos")]
+const MAC_PLAYERS: [&str; 2] = ["Music", "Spotify"];
+
+// Player chosen with `media_select_player`; `None` follows the active one
+static SELECTED_PLAYER: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+fn selected_player() -> Option<String> {
+    SELECTED_PLAYER.lock().unwrap().clone()
+}
+
+#[cfg(target_os = "macos")]
+fn osascript(script: &str) -> Result<String, String> {
+    platformLib::current()
+        .runner
+        .output("osascript", &["-e", script])
+}
+
+#[cfg(target_os = "macos")]
+fn mac_players() -> Vec<MediaPlayer> {
+    MAC_PLAYERS
+        .iter()
+        .filter(|app| {
+            osascript(&format!("application \"{}\" is running", app)).is_ok_and(|r| r == "true")
+        })
+        .map(|app| {
+            let state = osascript(&format!(
+                "tell application \"{}\" to player state as text",
+                app
+            ))
+            .unwrap_or_default();
+            MediaPlayer {
+                id: app.to_string(),
+                name: app.to_string(),
+                status: match state.as_str() {
+                    "playing" => PlaybackStatus::Playing,
+                    "paused" => PlaybackStatus::Paused,
+                    _ => PlaybackStatus::Stopped,
+                },
+                selected: false,
+            }
+        })
+        .collect()
+}
+
+// The selected app if running, else the one playing, else Music
+#[cfg(target_os = "macos")]
+fn mac_player() -> String {
+    let players = mac_players();
+    let selected = selected_player().filter(|id| players.iter().any(|p| &p.id == id));
+    selected
+        .or_else(|| {
+            players
+                .iter()
+                .find(|p| p.status == PlaybackStatus::Playing)
+                .or(players.first())
+                .map(|p| p.id.clone())
+        })
+        .unwrap_or_else(|| MAC_PLAYERS[0].to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn mac_now_playing(app: &str) -> Result<Option<NowPlaying>, String> {
+    // Spotify names the shuffle and repeat settings differently and only
+    // repeats the whole context
+    let (shuffle, repeat, artwork) = if app == "Spotify" {
+        ("shuffling", "repeating", " & linefeed & (artwork url of t)")
+    } else {
+        ("shuffle enabled", "song repeat", "")
+    };
+    let script = format!(
+        "tell application \"{app}\"\n\
+         if player state is stopped then return \"stopped\"\n\
+         set t to current track\n\
+         return (player state as text) & linefeed & (name of t) & linefeed & (artist of t) \
+         & linefeed & (album of t) & linefeed & (duration of t) & linefeed & (player position) \
+         & linefeed & ({shuffle} as text) & linefeed & ({repeat} as text){artwork}\n\
+         end tell"
+    );
+    let output = osascript(&script)?;
+    let lines: Vec<&str> = output.lines().collect();
+    if lines.len() < 8 {
+        return Ok(None);
+    }
+
+    let text = |i: usize| Some(lines[i].to_string()).filter(|s| !s.is_empty());
+    // AppleScript formats reals with the locale's decimal separator
+    let number = |i: usize| lines[i].replace(',', ".").parse::<f64>().ok();
+    // Spotify reports the duration in milliseconds, Music in seconds
+    let scale = if app == "Spotify" { 1000.0 } else { 1.0 };
+    Ok(Some(NowPlaying {
+        player: app.to_string(),
+        player_name: app.to_string(),
+        status: if lines[0] == "playing" {
+            PlaybackStatus::Playing
+        } else {
+            PlaybackStatus::Paused
+        },
+        title: text(1),
+        artist: text(2),
+        album: text(3),
+        duration: number(4).map(|d| d / scale),
+        position: number(5),
+        shuffle: lines[6].parse().ok(),
+        // Music answers off/one/all, Spotify true/false
+        loop_mode: match lines[7] {
+            "off" | "false" => Some(LoopMode::None),
+            "one" => Some(LoopMode::Track),
+            "all" | "true" => Some(LoopMode::Playlist),
+            _ => None,
+        },
+        artwork_url: lines
+            .get(8)
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty()),
+    }))
+}
+
+// Prepended to Windows media scripts: loads the system media transport
+// controls and defines `Await` for their WinRT async operations
+#[cfg(target_os = "windows")]
+const SMTC_PRELUDE: &str = r#"
+Add-Type -AssemblyName System.Runtime.WindowsRuntime
+$asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object { $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' } | Select-Object -First 1
+function Await($op, [Type]$type) { $task = $asTask.MakeGenericMethod($type).Invoke($null, @($op)); $task.Wait(-1) | Out-Null; $task.Result }
+$null = [Windows.Media.Control.GlobalSystemMediaTransportControlsSessionManager, Windows.Media.Control, ContentType = WindowsRuntime]
+$manager = Await ([Windows.Media.Control.GlobalSystemMediaTransportControlsSessionManager]::RequestAsync()) ([Windows.Media.Control.GlobalSystemMediaTransportControlsSessionManager])
+function Describe($s) {
+    $props = Await ($s.TryGetMediaPropertiesAsync()) ([Windows.Media.Control.GlobalSystemMediaTransportControlsSessionMediaProperties])
+    $timeline = $s.GetTimelineProperties()
+    $info = $s.GetPlaybackInfo()
+    [pscustomobject]@{ id = $s.SourceAppUserModelId; status = $info.PlaybackStatus.ToString(); title = $props.Title; artist = $props.Artist; album = $props.AlbumTitle; position = $timeline.Position.TotalSeconds; duration = $timeline.EndTime.TotalSeconds; shuffle = $info.IsShuffleActive; repeat = $(if ($null -ne $info.AutoRepeatMode) { $info.AutoRepeatMode.ToString() }) }
+}
+"#;
+
+#[cfg(target_os = "windows")]
+#[derive(serde::Deserialize)]
+struct WindowsSession {
+    id: String,
+    status: String,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    position: Option<f64>,
+    duration: Option<f64>,
+    shuffle: Option<bool>,
+    repeat: Option<String>,
+}
+
+#[cfg(target_os = "windows")]
+impl WindowsSession {
+    fn status(&self) -> PlaybackStatus {
+        match self.status.as_str() {
+            "Playing" => PlaybackStatus::Playing,
+            "Paused" => PlaybackStatus::Paused,
+            _ => PlaybackStatus::Stopped,
+        }
+    }
+
+    fn loop_mode(&self) -> Option<LoopMode> {
+        match self.repeat.as_deref()? {
+            "Track" => Some(LoopMode::Track),
+            "List" => Some(LoopMode::Playlist),
+            _ => Some(LoopMode::None),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn smtc(script: &str) -> Result<String, String> {
+    let script = format!("{}{}", SMTC_PRELUDE, script);
+    platformLib::current().runner.output(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", &script],
+    )
+}
+
+// Assigns `$session`: the selected app's session, else the current one
+#[cfg(target_os = "windows")]
+fn smtc_session_script() -> String {
+    let id = selected_player().unwrap_or_default().replace('\'', "''");
+    format!(
+        "$session = $manager.GetSessions() | Where-Object {{ $_.SourceAppUserModelId -eq '{}' }} | Select-Object -First 1\n\
+         if (-not $session) {{ $session = $manager.GetCurrentSession() }}\n\
+         if (-not $session) {{ throw 'No media session is active' }}\n",
+        id
+    )
+}
+
+#[cfg(target_os = "windows")]
+fn windows_sessions() -> Result<Vec<WindowsSession>, String> {
+    let json = smtc(
+        "ConvertTo-Json -Compress -InputObject @($manager.GetSessions() | ForEach-Object { Describe $_ })",
+    )?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+// Awaits a `Try...Async` session call such as `TryTogglePlayPauseAsync()`
+#[cfg(target_os = "windows")]
+fn windows_control(call: &str) -> Result<(), String> {
+    let result = smtc(&format!(
+        "{}Await ($session.{}) ([bool])",
+        smtc_session_script(),
+        call
+    ))?;
+    if result.eq_ignore_ascii_case("true") {
+        Ok(())
+    } else {
+        Err(format!("The media app rejected {}", call))
+    }
+}
+
+// Without a session API, fall back to the global media keys
+#[cfg(target_os = "windows")]
+fn nircmd_media_key(key: &str) -> Result<(), String> {
+    platformLib::current()
+        .runner
+        .output("nircmd.exe", &["sendkeypress", key])
+        .map(|_| ())
+        .map_err(|_| "Failed to send the media key on Windows; please install nircmd.exe".into())
+}
+
+#[cfg(target_os = "windows")]
+fn windows_control_or_key(call: &str, key: &str) -> Result<(), String> {
+    windows_control(call).or_else(|e| {
+        if selected_player().is_some() {
+            Err(e)
+        } else {
+            nircmd_media_key(key)
+        }
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn mac_tell(command: &str) -> Result<(), String> {
+    let app = mac_player();
+    osascript(&format!("tell application \"{}\" to {}", app, command))
+        .map(|_| ())
+        .map_err(|e| format!("{} failed in {}: {}", command, app, e))
+}
+
+#[cfg(target_os = "linux")]
+fn mpris<T>(
+    action: impl FnOnce(&mprisLib::MprisClient, &str) -> Result<T, String>,
+) -> Result<T, String> {
+    mprisLib::with_player(selected_player().as_deref(), action)
+}
 
 #[tauri::command]
 pub fn media_play() -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
-        let script = r#"tell application "Music" to play"#;
-        let status = Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .status()
-            .map_err(|e| e.to_string())?;
-
-        if status.success() {
-            Ok(())
-        } else {
-            Err("Failed to play media on macOS".into())
-        }
+        mac_tell("play")
     }
 
     #[cfg(target_os = "windows")]
     {
-        let status = Command::new("nircmd.exe")
-            .arg("sendkeypress")
-            .arg("media_play_pause")
-            .status();
+        windows_control("TryPlayAsync()")
+    }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err("Failed to play media on Windows; please install nircmd.exe".into()),
-        }
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.control(player, "Play"))
     }
 }
 
 #[tauri::command]
 pub fn media_pause() -> Result<(), String> {
-    media_play()
+    #[cfg(target_os = "macos")]
+    {
+        mac_tell("pause")
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows_control("TryPauseAsync()")
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.control(player, "Pause"))
+    }
 }
 
 #[tauri::command]
-pub fn media_skip() -> Result<(), String> {
+pub fn media_toggle() -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
-        let script = r#"tell application "Music" to next track"#;
-        let status = Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .status()
-            .map_err(|e| e.to_string())?;
+        mac_tell("playpause")
+    }
 
-        if status.success() {
-            Ok(())
-        } else {
-            Err("Failed to skip media on macOS".into())
-        }
+    #[cfg(target_os = "windows")]
+    {
+        windows_control_or_key("TryTogglePlayPauseAsync()", "media_play_pause")
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.control(player, "PlayPause"))
+    }
+}
+
+#[tauri::command]
+pub fn media_skip() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        mac_tell("next track")
     }
 
     #[cfg(target_os = "windows")]
     {
-        let status = Command::new("nircmd.exe")
-            .arg("sendkeypress")
-            .arg("media_next")
-            .status();
+        windows_control_or_key("TrySkipNextAsync()", "media_next")
+    }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err("Failed to skip media on Windows; please install nircmd.exe".into()),
-        }
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.control(player, "Next"))
     }
 }
 
@@ -73,162 +343,387 @@ pub fn media_skip() -> Result<(), String> {
 pub fn media_previous() -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
-        let script = r#"tell application "Music" to previous track"#;
-        let status = Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .status()
-            .map_err(|e| e.to_string())?;
-
-        if status.success() {
-            Ok(())
-        } else {
-            Err("Failed to go to previous media track on macOS".into())
-        }
+        mac_tell("previous track")
     }
 
     #[cfg(target_os = "windows")]
     {
-        let status = Command::new("nircmd.exe")
-            .arg("sendkeypress")
-            .arg("media_prev")
-            .status();
+        windows_control_or_key("TrySkipPreviousAsync()", "media_prev")
+    }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err(
-                "Failed to go to previous media track on Windows; please install nircmd.exe".into(),
-            ),
-        }
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.control(player, "Previous"))
     }
 }
 
+// Jumps `seconds` forward, or backward when negative
 #[tauri::command]
-pub fn set_volume(volume: u8) -> Result<(), String> {
-    if volume > 100 {
-        return Err("Volume must be between 0 and 100".into());
+pub fn media_seek(seconds: f64) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        mac_tell(&format!(
+            "set player position to (player position + {})",
+            seconds
+        ))
     }
 
+    #[cfg(target_os = "windows")]
+    {
+        // Timeline positions are in 100ns ticks
+        let ticks = (seconds * 10_000_000.0) as i64;
+        windows_control(&format!(
+            "TryChangePlaybackPositionAsync([Math]::Max(0, $session.GetTimelineProperties().Position.Ticks + {}))",
+            ticks
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.seek(player, seconds))
+    }
+}
+
+#[tauri::command]
+pub fn media_set_position(seconds: f64) -> Result<(), String> {
+    let seconds = seconds.max(0.0);
+
     #[cfg(target_os = "macos")]
     {
-        let script = format!("set volume output volume {}", volume);
-        let status = Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .status()
-            .map_err(|e| e.to_string())?;
+        mac_tell(&format!("set player position to {}", seconds))
+    }
 
-        if status.success() {
-            Ok(())
+    #[cfg(target_os = "windows")]
+    {
+        let ticks = (seconds * 10_000_000.0) as i64;
+        windows_control(&format!("TryChangePlaybackPositionAsync({})", ticks))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.set_position(player, seconds))
+    }
+}
+
+#[tauri::command]
+pub fn media_set_shuffle(enabled: bool) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        if mac_player() == "Spotify" {
+            mac_tell(&format!("set shuffling to {}", enabled))
         } else {
-            Err("Failed to set volume on macOS".into())
+            mac_tell(&format!("set shuffle enabled to {}", enabled))
         }
     }
 
     #[cfg(target_os = "windows")]
     {
-        let status = Command::new("nircmd.exe")
-            .args(&["setsysvolume", &(volume as u32 * 65535 / 100).to_string()])
-            .status();
+        let flag = if enabled { "$true" } else { "$false" };
+        windows_control(&format!("TryChangeShuffleActiveAsync({})", flag))
+    }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err("Failed to set volume on Windows; please install nircmd.exe".into()),
-        }
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.set_shuffle(player, enabled))
     }
 }
 
 #[tauri::command]
-pub fn mute_volume() -> Result<(), String> {
+pub fn media_set_loop(mode: LoopMode) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
-        let script = format!("set volume output volume {}", 0);
-        let status = Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .status()
-            .map_err(|e| e.to_string())?;
-
-        if status.success() {
-            Ok(())
+        if mac_player() == "Spotify" {
+            // Spotify's scripting only repeats the whole context
+            match mode {
+                LoopMode::None => mac_tell("set repeating to false"),
+                LoopMode::Playlist => mac_tell("set repeating to true"),
+                LoopMode::Track => Err("Spotify can't repeat a single track from scripts".into()),
+            }
         } else {
-            Err("Failed to set volume on macOS".into())
+            let repeat = match mode {
+                LoopMode::None => "off",
+                LoopMode::Track => "one",
+                LoopMode::Playlist => "all",
+            };
+            mac_tell(&format!("set song repeat to {}", repeat))
         }
     }
 
     #[cfg(target_os = "windows")]
     {
-        let status = Command::new("nircmd.exe")
-            .args(&["setsysvolume", &(0 as u32 * 65535 / 100).to_string()])
-            .status();
+        // MediaPlaybackAutoRepeatMode: None = 0, Track = 1, List = 2
+        let repeat = match mode {
+            LoopMode::None => 0,
+            LoopMode::Track => 1,
+            LoopMode::Playlist => 2,
+        };
+        windows_control(&format!("TryChangeAutoRepeatModeAsync({})", repeat))
+    }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err("Failed to set volume on Windows; please install nircmd.exe".into()),
-        }
+    #[cfg(target_os = "linux")]
+    {
+        mpris(|client, player| client.set_loop(player, mode))
     }
 }
 
 #[tauri::command]
-pub fn increase_volume(delta: u8) -> Result<(), String> {
+pub fn media_list_players() -> Result<Vec<MediaPlayer>, String> {
+    #[cfg(target_os = "linux")]
+    let mut players = mprisLib::MprisClient::session()?.list_players()?;
+
     #[cfg(target_os = "macos")]
-    {
-        let output = Command::new("osascript")
-            .arg("-e")
-            .arg("output volume of (get volume settings)")
-            .output()
-            .map_err(|e| e.to_string())?;
+    let mut players = mac_players();
 
-        let current_vol_str = String::from_utf8_lossy(&output.stdout);
-        let current_vol: u8 = current_vol_str.trim().parse().unwrap_or(50);
-        let new_vol = (current_vol.saturating_add(delta)).min(100);
+    #[cfg(target_os = "windows")]
+    let mut players: Vec<MediaPlayer> = windows_sessions()?
+        .into_iter()
+        .map(|session| MediaPlayer {
+            status: session.status(),
+            name: session.id.clone(),
+            id: session.id,
+            selected: false,
+        })
+        .collect();
+
+    let selected = selected_player();
+    for player in &mut players {
+        player.selected = selected.as_deref() == Some(player.id.as_str());
+    }
+    Ok(players)
+}
 
-        set_volume(new_vol)
+// Makes media commands target `id`; `None` goes back to the active player
+#[tauri::command]
+pub fn media_select_player(id: Option<String>) -> Result<(), String> {
+    if let Some(id) = &id {
+        if !media_list_players()?.iter().any(|p| &p.id == id) {
+            return Err(format!("No media player '{}' is running", id));
+        }
+    }
+    *SELECTED_PLAYER.lock().unwrap() = id;
+    Ok(())
+}
+
+// What the targeted player is playing, or `None` when nothing is
+#[tauri::command]
+pub fn media_now_playing() -> Result<Option<NowPlaying>, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let client = mprisLib::MprisClient::session()?;
+        match mprisLib::target_player(&client, selected_player().as_deref()) {
+            Ok(player) => client.now_playing(&player).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let app = mac_player();
+        if mac_players().iter().any(|p| p.id == app) {
+            mac_now_playing(&app)
+        } else {
+            Ok(None)
+        }
     }
 
     #[cfg(target_os = "windows")]
     {
-        let delta_value = delta as u32 * 65535 / 100;
-        let status = Command::new("nircmd.exe")
-            .args(&["changesysvolume", &delta_value.to_string()])
-            .status();
+        let json = smtc(&format!(
+            "{}Describe $session | ConvertTo-Json -Compress",
+            smtc_session_script()
+        ));
+        let Ok(json) = json else {
+            return Ok(None);
+        };
+        let session: WindowsSession = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        Ok(Some(NowPlaying {
+            status: session.status(),
+            player_name: session.id.clone(),
+            player: session.id,
+            title: session.title.filter(|s| !s.is_empty()),
+            artist: session.artist.filter(|s| !s.is_empty()),
+            album: session.album.filter(|s| !s.is_empty()),
+            artwork_url: None,
+            position: session.position,
+            duration: session.duration.filter(|&d| d > 0.0),
+            loop_mode: session.loop_mode(),
+            shuffle: session.shuffle,
+        }))
+    }
+}
+
+// Emit `media-changed` with the new `NowPlaying` when the track, player or
+// playback status changes. Linux is told by MPRIS signals; elsewhere the player
+// is polled, and only while the launcher is visible since each poll spawns
+// PowerShell or osascript
+pub fn watch(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        // Position moves on its own; the frontend can extrapolate it
+        let key = |now: &Option<NowPlaying>| {
+            now.clone().map(|mut now| {
+                now.position = None;
+                now
+            })
+        };
+        let mut last = None;
+        let mut check = || {
+            let current = media_now_playing().unwrap_or(None);
+            if key(&current) != key(&last) {
+                let _ = app.emit("media-changed", &current);
+                last = current;
+            }
+        };
+
+        #[cfg(target_os = "linux")]
+        {
+            check();
+            let watched = mprisLib::MprisClient::session().and_then(|client| client.watch(check));
+            if let Err(e) = watched {
+                eprintln!("Stopped watching media players: {}", e);
+            }
+        }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err("Failed to increase volume on Windows; please install nircmd.exe".into()),
+        #[cfg(not(target_os = "linux"))]
+        loop {
+            let visible = app
+                .get_webview_window("main")
+                .is_some_and(|window| window.is_visible().unwrap_or(false));
+            if visible {
+                check();
+            }
+            std::thread::sleep(NOW_PLAYING_POLL);
         }
+    });
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct VolumeState {
+    // Percent of the output's range
+    pub level: u8,
+    pub muted: bool,
+}
+
+#[tauri::command]
+pub fn get_volume() -> Result<VolumeState, String> {
+    platformLib::current().volume.volume()
+}
+
+#[tauri::command]
+pub fn set_volume(volume: u8) -> Result<(), String> {
+    if volume > 100 {
+        return Err("Volume must be between 0 and 100".into());
     }
+    platformLib::current().volume.set_volume(volume)
 }
 
+// Toggles mute; the level is kept so unmuting restores it
 #[tauri::command]
-pub fn decrease_volume(delta: u8) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        let output = Command::new("osascript")
-            .arg("-e")
-            .arg("output volume of (get volume settings)")
-            .output()
-            .map_err(|e| e.to_string())?;
+pub fn mute_volume() -> Result<VolumeState, String> {
+    let control = platformLib::current().volume.clone();
+    control.set_muted(!control.volume()?.muted)?;
+    control.volume()
+}
 
-        let current_vol_str = String::from_utf8_lossy(&output.stdout);
-        let current_vol: u8 = current_vol_str.trim().parse().unwrap_or(50);
-        let new_vol = current_vol.saturating_sub(delta);
+// Returns the state read back, which reflects the backend's own rounding
+fn change_volume(control: &dyn VolumeControl, delta: i16) -> Result<VolumeState, String> {
+    let level = (control.volume()?.level as i16 + delta).clamp(0, 100) as u8;
+    control.set_volume(level)?;
+    control.volume()
+}
 
-        set_volume(new_vol)
+#[tauri::command]
+pub fn increase_volume(delta: u8) -> Result<VolumeState, String> {
+    change_volume(platformLib::current().volume.as_ref(), delta as i16)
+}
+
+#[tauri::command]
+pub fn decrease_volume(delta: u8) -> Result<VolumeState, String> {
+    change_volume(platformLib::current().volume.as_ref(), -(delta as i16))
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MicrophoneState {
+    // Name of the default input device
+    pub device: String,
+    pub level: Option<u8>,
+    pub muted: bool,
+}
+
+#[tauri::command]
+pub fn get_microphone_state() -> Result<MicrophoneState, String> {
+    let audio = platformLib::current().audio_devices.clone();
+    let device = audioDevicesLib::default_device(audio.as_ref(), DeviceKind::Input)?;
+    Ok(MicrophoneState {
+        device: device.name,
+        level: device.volume,
+        muted: device.muted.unwrap_or(false),
+    })
+}
+
+// Mutes or unmutes the default input, then emits `microphone-changed` and
+// shows a notification so the new state is visible during a call
+#[tauri::command]
+pub fn toggle_microphone_mute(app: tauri::AppHandle) -> Result<MicrophoneState, String> {
+    let audio = platformLib::current().audio_devices.clone();
+    let device = audioDevicesLib::default_device(audio.as_ref(), DeviceKind::Input)?;
+    audio.set_device_muted(
+        DeviceKind::Input,
+        &device.id,
+        !device.muted.unwrap_or(false),
+    )?;
+
+    let state = get_microphone_state()?;
+    let _ = app.emit("microphone-changed", &state);
+    let _ = app
+        .notification()
+        .builder()
+        .title(if state.muted {
+            "Microphone muted"
+        } else {
+            "Microphone on"
+        })
+        .body(&state.device)
+        .show();
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An output that only takes levels in steps of `step` percent
+    struct SteppedVolume {
+        step: u8,
+        level: Mutex<u8>,
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        let delta_value = delta as u32 * 65535 / 100;
-        let neg_delta = (0u32).wrapping_sub(delta_value);
+    impl VolumeControl for SteppedVolume {
+        fn volume(&self) -> Result<VolumeState, String> {
+            Ok(VolumeState {
+                level: *self.level.lock().unwrap(),
+                muted: false,
+            })
+        }
 
-        let status = Command::new("nircmd.exe")
-            .args(&["changesysvolume", &neg_delta.to_string()])
-            .status();
+        fn set_volume(&self, percent: u8) -> Result<(), String> {
+            *self.level.lock().unwrap() = percent / self.step * self.step;
+            Ok(())
+        }
 
-        match status {
-            Ok(s) if s.success() => Ok(()),
-            _ => Err("Failed to decrease volume on Windows; please install nircmd.exe".into()),
+        fn set_muted(&self, _muted: bool) -> Result<(), String> {
+            Ok(())
         }
     }
+
+    #[test]
+    fn volume_changes_report_the_level_read_back() {
+        let output = SteppedVolume {
+            step: 6,
+            level: Mutex::new(48),
+        };
+        assert_eq!(change_volume(&output, 5).unwrap().level, 48);
+        assert_eq!(change_volume(&output, 10).unwrap().level, 54);
+        assert_eq!(change_volume(&output, 80).unwrap().level, 96);
+        assert_eq!(change_volume(&output, -120).unwrap().level, 0);
+    }
 }
@@ -0,0 +1,46 @@
+// Media player types shared by `mediaLib` and the platform backends it uses
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoopMode {
+    None,
+    Track,
+    Playlist,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MediaPlayer {
+    // MPRIS bus name, macOS app name or Windows app id
+    pub id: String,
+    pub name: String,
+    pub status: PlaybackStatus,
+    pub selected: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+pub struct NowPlaying {
+    pub player: String,
+    pub player_name: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub artwork_url: Option<String>,
+    // Seconds
+    pub position: Option<f64>,
+    pub duration: Option<f64>,
+    pub status: PlaybackStatus,
+    // `None` when the player doesn't report them
+    pub shuffle: Option<bool>,
+    pub loop_mode: Option<LoopMode>,
+}
@@ -0,0 +1,568 @@
+// Media control on Linux through MPRIS, which players such as Spotify, VLC,
+// Firefox and mpv expose on the D-Bus session bus.
+//
+// The session bus comes from `DBUS_SESSION_BUS_ADDRESS`, so pointing it at a
+// private `dbus-daemon` is enough to run against a stand-in player.
+
+use crate::mediaTypesLib::{LoopMode, MediaPlayer, NowPlaying, PlaybackStatus};
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::{Connection, MessageIterator, Proxy};
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{ObjectPath, OwnedValue};
+use zbus::MatchRule;
+
+const BUS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+// MPRIS times are in microseconds
+const MICROS_PER_SECOND: f64 = 1_000_000.0;
+
+// Shared by every media command and the watcher; connections are cheap to
+// clone but not to open
+static SESSION: OnceCell<Connection> = OnceCell::new();
+
+fn parse_status(status: &str) -> PlaybackStatus {
+    match status {
+        "Playing" => PlaybackStatus::Playing,
+        "Paused" => PlaybackStatus::Paused,
+        _ => PlaybackStatus::Stopped,
+    }
+}
+
+fn parse_loop(status: &str) -> LoopMode {
+    match status {
+        "Track" => LoopMode::Track,
+        "Playlist" => LoopMode::Playlist,
+        _ => LoopMode::None,
+    }
+}
+
+fn metadata_string(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    metadata
+        .get(key)?
+        .downcast_ref::<String>()
+        .ok()
+        .filter(|s| !s.is_empty())
+}
+
+// `xesam:artist` is a list; some players send a plain string anyway
+fn metadata_list(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    let value = metadata.get(key)?;
+    let joined = match Vec::<String>::try_from(value.try_clone().ok()?) {
+        Ok(list) => list.join(", "),
+        Err(_) => value.downcast_ref::<String>().ok()?,
+    };
+    (!joined.is_empty()).then_some(joined)
+}
+
+// `mpris:length` should be an i64 but some players send a u64
+fn metadata_micros(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<f64> {
+    let value = metadata.get(key)?;
+    value
+        .downcast_ref::<i64>()
+        .map(|v| v as f64)
+        .or_else(|_| value.downcast_ref::<u64>().map(|v| v as f64))
+        .ok()
+        .map(|micros| micros / MICROS_PER_SECOND)
+}
+
+// Signals for a player's properties changing, and for players starting or quitting
+fn change_rules() -> zbus::Result<[MatchRule<'static>; 2]> {
+    let properties = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path(OBJECT_PATH)?
+        .arg(0, PLAYER_INTERFACE)?
+        .build();
+    let players = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .sender("org.freedesktop.DBus")?
+        .interface("org.freedesktop.DBus")?
+        .member("NameOwnerChanged")?
+        .arg0ns("org.mpris.MediaPlayer2")?
+        .build();
+    Ok([properties, players])
+}
+
+pub struct MprisClient {
+    conn: Connection,
+}
+
+impl MprisClient {
+    pub fn new(conn: Connection) -> Self {
+        MprisClient { conn }
+    }
+
+    // The session bus, connected on first use
+    pub fn session() -> Result<Self, String> {
+        let conn = SESSION
+            .get_or_try_init(Connection::session)
+            .map_err(|e| format!("Failed to connect to the session bus: {}", e))?;
+        Ok(Self::new(conn.clone()))
+    }
+
+    /// Calls `changed` whenever a player's state changes or a player starts or
+    /// quits, until the bus connection is lost.
+    pub fn watch(&self, mut changed: impl FnMut()) -> Result<(), String> {
+        let rules = change_rules().map_err(|e| e.to_string())?;
+        let messages = MessageIterator::from(&self.conn);
+        let dbus = DBusProxy::new(&self.conn).map_err(|e| e.to_string())?;
+        for rule in &rules {
+            dbus.add_match_rule(rule.clone())
+                .map_err(|e| format!("Failed to listen for player changes: {}", e))?;
+        }
+
+        for message in messages {
+            let message = message.map_err(|e| e.to_string())?;
+            if rules
+                .iter()
+                .any(|rule| rule.matches(&message).unwrap_or(false))
+            {
+                changed();
+            }
+        }
+        Ok(())
+    }
+
+    /// Bus names of every running MPRIS player.
+    pub fn players(&self) -> Result<Vec<String>, String> {
+        let names = DBusProxy::new(&self.conn)
+            .map_err(|e| e.to_string())?
+            .list_names()
+            .map_err(|e| e.to_string())?;
+        let mut players: Vec<String> = names
+            .into_iter()
+            .map(|name| name.to_string())
+            .filter(|name| name.starts_with(BUS_PREFIX))
+            .collect();
+        players.sort();
+        Ok(players)
+    }
+
+    fn proxy(&self, bus_name: &str, interface: &'static str) -> Result<Proxy<'_>, String> {
+        Proxy::new(&self.conn, bus_name.to_string(), OBJECT_PATH, interface)
+            .map_err(|e| e.to_string())
+    }
+
+    fn player(&self, bus_name: &str) -> Result<Proxy<'_>, String> {
+        self.proxy(bus_name, PLAYER_INTERFACE)
+    }
+
+    /// The player's display name, e.g. "Spotify" or "VLC media player".
+    pub fn identity(&self, bus_name: &str) -> String {
+        self.proxy(bus_name, ROOT_INTERFACE)
+            .and_then(|root| root.get_property("Identity").map_err(|e| e.to_string()))
+            .unwrap_or_else(|_| {
+                let name = bus_name.trim_start_matches(BUS_PREFIX);
+                // Instances are suffixed, e.g. "vlc.instance1234"
+                name.split('.').next().unwrap_or(name).to_string()
+            })
+    }
+
+    pub fn playback_status(&self, bus_name: &str) -> Result<String, String> {
+        self.player(bus_name)?
+            .get_property("PlaybackStatus")
+            .map_err(|e| e.to_string())
+    }
+
+    /// The player that is playing, else one that is paused, else any player.
+    pub fn active_player(&self) -> Result<String, String> {
+        let players = self.players()?;
+        let status = |player: &String| self.playback_status(player).unwrap_or_default();
+        ["Playing", "Paused"]
+            .iter()
+            .find_map(|wanted| players.iter().find(|p| status(p) == *wanted))
+            .or(players.first())
+            .cloned()
+            .ok_or_else(|| "No media player is running".to_string())
+    }
+
+    pub fn list_players(&self) -> Result<Vec<MediaPlayer>, String> {
+        Ok(self
+            .players()?
+            .into_iter()
+            .map(|id| MediaPlayer {
+                name: self.identity(&id),
+                status: parse_status(&self.playback_status(&id).unwrap_or_default()),
+                selected: false,
+                id,
+            })
+            .collect())
+    }
+
+    pub fn now_playing(&self, bus_name: &str) -> Result<NowPlaying, String> {
+        let player = self.player(bus_name)?;
+        let metadata: HashMap<String, OwnedValue> =
+            player.get_property("Metadata").map_err(|e| e.to_string())?;
+        let position = player
+            .get_property::<i64>("Position")
+            .ok()
+            .map(|micros| micros as f64 / MICROS_PER_SECOND);
+
+        Ok(NowPlaying {
+            player: bus_name.to_string(),
+            player_name: self.identity(bus_name),
+            title: metadata_string(&metadata, "xesam:title"),
+            artist: metadata_list(&metadata, "xesam:artist"),
+            album: metadata_string(&metadata, "xesam:album"),
+            artwork_url: metadata_string(&metadata, "mpris:artUrl"),
+            position,
+            duration: metadata_micros(&metadata, "mpris:length"),
+            status: parse_status(&self.playback_status(bus_name)?),
+            // Both are optional in MPRIS
+            shuffle: player.get_property("Shuffle").ok(),
+            loop_mode: player
+                .get_property::<String>("LoopStatus")
+                .ok()
+                .map(|status| parse_loop(&status)),
+        })
+    }
+
+    /// Calls an argument-less `Player` method such as `PlayPause` or `Next`.
+    pub fn control(&self, bus_name: &str, method: &str) -> Result<(), String> {
+        self.player(bus_name)?
+            .call_method(method, &())
+            .map(|_| ())
+            .map_err(|e| format!("{} failed: {}", method, e))
+    }
+
+    /// Moves the playback position by `offset` seconds, backwards if negative.
+    pub fn seek(&self, bus_name: &str, offset: f64) -> Result<(), String> {
+        let micros = (offset * MICROS_PER_SECOND) as i64;
+        self.player(bus_name)?
+            .call_method("Seek", &(micros,))
+            .map(|_| ())
+            .map_err(|e| format!("Seek failed: {}", e))
+    }
+
+    pub fn set_position(&self, bus_name: &str, position: f64) -> Result<(), String> {
+        let player = self.player(bus_name)?;
+        let metadata: HashMap<String, OwnedValue> =
+            player.get_property("Metadata").map_err(|e| e.to_string())?;
+        // SetPosition is ignored unless it names the current track
+        let track = metadata
+            .get("mpris:trackid")
+            .and_then(|id| id.downcast_ref::<ObjectPath>().ok())
+            .ok_or("The player doesn't report a track id")?;
+        let micros = (position.max(0.0) * MICROS_PER_SECOND) as i64;
+        player
+            .call_method("SetPosition", &(track, micros))
+            .map(|_| ())
+            .map_err(|e| format!("SetPosition failed: {}", e))
+    }
+
+    pub fn set_shuffle(&self, bus_name: &str, enabled: bool) -> Result<(), String> {
+        self.player(bus_name)?
+            .set_property("Shuffle", enabled)
+            .map_err(|e| format!("Setting shuffle failed: {}", e))
+    }
+
+    pub fn set_loop(&self, bus_name: &str, mode: LoopMode) -> Result<(), String> {
+        let status = match mode {
+            LoopMode::None => "None",
+            LoopMode::Track => "Track",
+            LoopMode::Playlist => "Playlist",
+        };
+        self.player(bus_name)?
+            .set_property("LoopStatus", status)
+            .map_err(|e| format!("Setting the loop mode failed: {}", e))
+    }
+}
+
+// `preferred` if it is still running, else the active player
+pub fn target_player(client: &MprisClient, preferred: Option<&str>) -> Result<String, String> {
+    match preferred {
+        Some(id) if client.players()?.iter().any(|p| p == id) => Ok(id.to_string()),
+        _ => client.active_player(),
+    }
+}
+
+// Runs `action` against the preferred or active player of the session bus
+pub fn with_player<T>(
+    preferred: Option<&str>,
+    action: impl FnOnce(&MprisClient, &str) -> Result<T, String>,
+) -> Result<T, String> {
+    let client = MprisClient::session()?;
+    let player = target_player(&client, preferred)?;
+    action(&client, &player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbusTestLib::PrivateBus;
+    use std::sync::{Arc, Mutex};
+    use zbus::zvariant::Value;
+
+    fn owned(value: Value<'_>) -> OwnedValue {
+        value.try_into().unwrap()
+    }
+
+    #[test]
+    fn parses_statuses() {
+        assert_eq!(parse_status("Playing"), PlaybackStatus::Playing);
+        assert_eq!(parse_status("Paused"), PlaybackStatus::Paused);
+        assert_eq!(parse_status("Stopped"), PlaybackStatus::Stopped);
+        assert_eq!(parse_status(""), PlaybackStatus::Stopped);
+        assert_eq!(parse_loop("Track"), LoopMode::Track);
+        assert_eq!(parse_loop("Playlist"), LoopMode::Playlist);
+        assert_eq!(parse_loop("None"), LoopMode::None);
+    }
+
+    #[test]
+    fn reads_metadata_values() {
+        let metadata = HashMap::from([
+            ("xesam:title".to_string(), owned(Value::from("Song"))),
+            ("xesam:album".to_string(), owned(Value::from(""))),
+            (
+                "xesam:artist".to_string(),
+                owned(Value::from(vec!["A", "B"])),
+            ),
+            ("xesam:albumArtist".to_string(), owned(Value::from("C"))),
+            (
+                "mpris:length".to_string(),
+                owned(Value::from(90_500_000i64)),
+            ),
+            ("vlc:length".to_string(), owned(Value::from(2_000_000u64))),
+        ]);
+
+        assert_eq!(
+            metadata_string(&metadata, "xesam:title").as_deref(),
+            Some("Song")
+        );
+        assert_eq!(metadata_string(&metadata, "xesam:album"), None);
+        assert_eq!(metadata_string(&metadata, "missing"), None);
+        assert_eq!(
+            metadata_list(&metadata, "xesam:artist").as_deref(),
+            Some("A, B")
+        );
+        assert_eq!(
+            metadata_list(&metadata, "xesam:albumArtist").as_deref(),
+            Some("C")
+        );
+        assert_eq!(metadata_micros(&metadata, "mpris:length"), Some(90.5));
+        assert_eq!(metadata_micros(&metadata, "vlc:length"), Some(2.0));
+        assert_eq!(metadata_micros(&metadata, "xesam:title"), None);
+    }
+
+    struct StandInRoot;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl StandInRoot {
+        #[zbus(property)]
+        fn identity(&self) -> String {
+            "Stand-in Player".into()
+        }
+    }
+
+    struct StandInPlayer {
+        status: &'static str,
+        shuffle: bool,
+        loop_status: String,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl StandInPlayer {
+        fn next(&self) {
+            self.calls.lock().unwrap().push("Next".into());
+        }
+
+        fn seek(&self, offset: i64) {
+            self.calls.lock().unwrap().push(format!("Seek {}", offset));
+        }
+
+        fn set_position(&self, track: ObjectPath<'_>, position: i64) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("SetPosition {} {}", track, position));
+        }
+
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            self.status.into()
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            HashMap::from([
+                (
+                    "mpris:trackid".to_string(),
+                    owned(Value::from(ObjectPath::try_from("/track/7").unwrap())),
+                ),
+                ("xesam:title".to_string(), owned(Value::from("Song"))),
+                ("xesam:artist".to_string(), owned(Value::from(vec!["Band"]))),
+                (
+                    "mpris:length".to_string(),
+                    owned(Value::from(200_000_000i64)),
+                ),
+            ])
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            12_000_000
+        }
+
+        #[zbus(property)]
+        fn shuffle(&self) -> bool {
+            self.shuffle
+        }
+
+        #[zbus(property)]
+        fn set_shuffle(&mut self, shuffle: bool) {
+            self.shuffle = shuffle;
+        }
+
+        #[zbus(property)]
+        fn loop_status(&self) -> String {
+            self.loop_status.clone()
+        }
+
+        #[zbus(property)]
+        fn set_loop_status(&mut self, status: String) {
+            self.loop_status = status;
+        }
+    }
+
+    fn serve_player(
+        bus: &PrivateBus,
+        name: &str,
+        status: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    ) -> Connection {
+        let player = StandInPlayer {
+            status,
+            shuffle: false,
+            loop_status: "None".into(),
+            calls,
+        };
+        bus.builder()
+            .name(format!("{}{}", BUS_PREFIX, name))
+            .unwrap()
+            .serve_at(OBJECT_PATH, StandInRoot)
+            .unwrap()
+            .serve_at(OBJECT_PATH, player)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn properties_changed(conn: &Connection, interface: &str) {
+        conn.emit_signal(
+            None::<&str>,
+            OBJECT_PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            &(
+                interface,
+                HashMap::<String, OwnedValue>::new(),
+                Vec::<String>::new(),
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn watch_reports_player_changes() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed; skipping");
+            return;
+        };
+        let (sender, changes) = std::sync::mpsc::channel();
+        let client = MprisClient::new(bus.connect());
+        std::thread::spawn(move || {
+            let _ = client.watch(|| {
+                let _ = sender.send(());
+            });
+        });
+        let drain = || while changes.try_recv().is_ok() {};
+        let timeout = std::time::Duration::from_millis(300);
+
+        // Keep signalling until the watcher has subscribed
+        let music = serve_player(&bus, "music", "Playing", Arc::default());
+        let subscribed = (0..20).any(|_| {
+            properties_changed(&music, PLAYER_INTERFACE);
+            changes.recv_timeout(timeout).is_ok()
+        });
+        assert!(subscribed);
+
+        std::thread::sleep(timeout);
+        drain();
+        properties_changed(&music, ROOT_INTERFACE);
+        assert!(changes.recv_timeout(timeout).is_err());
+
+        let _video = serve_player(&bus, "video", "Paused", Arc::default());
+        assert!(changes.recv_timeout(timeout * 5).is_ok());
+        drain();
+        properties_changed(&music, PLAYER_INTERFACE);
+        assert!(changes.recv_timeout(timeout * 5).is_ok());
+    }
+
+    #[test]
+    fn controls_a_stand_in_player_on_a_private_bus() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed; skipping");
+            return;
+        };
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let _paused = serve_player(&bus, "idle", "Paused", Arc::default());
+        let _playing = serve_player(&bus, "music", "Playing", calls.clone());
+        let client = MprisClient::new(bus.connect());
+
+        let players = client.players().unwrap();
+        assert_eq!(
+            players,
+            vec![
+                "org.mpris.MediaPlayer2.idle".to_string(),
+                "org.mpris.MediaPlayer2.music".to_string()
+            ]
+        );
+        let music = client.active_player().unwrap();
+        assert_eq!(music, "org.mpris.MediaPlayer2.music");
+        assert_eq!(client.identity(&music), "Stand-in Player");
+        assert_eq!(
+            target_player(&client, Some("org.mpris.MediaPlayer2.idle")).unwrap(),
+            "org.mpris.MediaPlayer2.idle"
+        );
+        assert_eq!(
+            target_player(&client, Some("org.mpris.MediaPlayer2.gone")).unwrap(),
+            music
+        );
+
+        let now = client.now_playing(&music).unwrap();
+        assert_eq!(now.player_name, "Stand-in Player");
+        assert_eq!(now.title.as_deref(), Some("Song"));
+        assert_eq!(now.artist.as_deref(), Some("Band"));
+        assert_eq!(now.album, None);
+        assert_eq!(now.position, Some(12.0));
+        assert_eq!(now.duration, Some(200.0));
+        assert_eq!(now.status, PlaybackStatus::Playing);
+        assert_eq!(now.shuffle, Some(false));
+        assert_eq!(now.loop_mode, Some(LoopMode::None));
+
+        client.control(&music, "Next").unwrap();
+        client.seek(&music, -5.5).unwrap();
+        client.set_position(&music, 30.0).unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "Next".to_string(),
+                "Seek -5500000".to_string(),
+                "SetPosition /track/7 30000000".to_string()
+            ]
+        );
+        assert!(client.control(&music, "Stop").is_err());
+
+        client.set_shuffle(&music, true).unwrap();
+        client.set_loop(&music, LoopMode::Playlist).unwrap();
+        let now = client.now_playing(&music).unwrap();
+        assert_eq!(now.shuffle, Some(true));
+        assert_eq!(now.loop_mode, Some(LoopMode::Playlist));
+    }
+}
@@ -0,0 +1,495 @@
+// Dynamic placeholders inside snippets:
+//   {date} {date:%d/%m/%Y} {time} {time:%H:%M:%S} {datetime}
+//   {clipboard} {uuid} {cursor}
+//   {argument name="Customer" default="ACME"}
+//   {snippet name="signature"}
+// `{{` and `}}` produce literal braces; any other `{...}`, including a known
+// name followed by text it doesn't take, is left untouched. A `}` inside a
+// quoted attribute value doesn't end the placeholder.
+
+use arboard::Clipboard;
+use chrono::format::{Item, StrftimeItems};
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Guards against runaway `{snippet}` includes
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+pub type SnippetLookup<'a> = &'a dyn Fn(&str) -> Result<String, String>;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Argument {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Expansion {
+    pub text: String,
+    // Char offset of `{cursor}` in `text`, if the snippet has one
+    pub cursor: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Date(Option<String>),
+    Time(Option<String>),
+    DateTime(Option<String>),
+    Clipboard,
+    Uuid,
+    Cursor,
+    Argument(Argument),
+    Include(String),
+}
+
+// Parses `key="value" other=value` pairs
+pub fn parse_attributes(input: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = input.trim_start();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().to_string();
+        let after = after.trim_start();
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            quoted.split_once('"').unwrap_or((quoted, ""))
+        } else {
+            after.split_once(char::is_whitespace).unwrap_or((after, ""))
+        };
+        attrs.insert(key, value.to_string());
+        rest = remaining.trim_start();
+    }
+    attrs
+}
+
+fn parse_placeholder(inner: &str) -> Option<Segment> {
+    let head_end = inner
+        .find(|c: char| c == ':' || c.is_whitespace())
+        .unwrap_or(inner.len());
+    let (head, rest) = inner.split_at(head_end);
+    // `Some(None)` without a format, `None` when something else follows
+    let format = match rest.strip_prefix(':') {
+        Some(format) => Some(Some(format.to_string())),
+        None => rest.is_empty().then_some(None),
+    };
+    let attrs = |allowed: &[&str]| {
+        let attrs = parse_attributes(rest);
+        let known = attrs.keys().all(|key| allowed.contains(&key.as_str()));
+        known.then_some(attrs)
+    };
+
+    let segment = match head {
+        "date" => Segment::Date(format?),
+        "time" => Segment::Time(format?),
+        "datetime" => Segment::DateTime(format?),
+        "clipboard" if rest.is_empty() => Segment::Clipboard,
+        "uuid" if rest.is_empty() => Segment::Uuid,
+        "cursor" if rest.is_empty() => Segment::Cursor,
+        "argument" => {
+            let mut attrs = attrs(&["name", "default"])?;
+            Segment::Argument(Argument {
+                name: attrs.remove("name")?,
+                default: attrs.remove("default"),
+            })
+        }
+        "snippet" => Segment::Include(attrs(&["name"])?.remove("name")?),
+        _ => return None,
+    };
+    Some(segment)
+}
+
+// Offset of the `}` closing the placeholder that opens `tail`, skipping
+// quoted attribute values; `None` if another `{` comes first
+fn placeholder_end(tail: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in tail.char_indices().skip(1) {
+        match c {
+            '"' => quoted = !quoted,
+            '}' if !quoted => return Some(i),
+            '{' if !quoted => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse(template: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        text.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            text.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        if let Some(after) = tail.strip_prefix('}') {
+            text.push('}');
+            rest = after;
+            continue;
+        }
+
+        match placeholder_end(tail) {
+            Some(close) => {
+                let inner = &tail[1..close];
+                match parse_placeholder(inner) {
+                    Some(segment) => {
+                        if !text.is_empty() {
+                            segments.push(Segment::Text(std::mem::take(&mut text)));
+                        }
+                        segments.push(segment);
+                    }
+                    None => text.push_str(&tail[..close + 1]),
+                }
+                rest = &tail[close + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+fn format_now(format: &str) -> Result<String, String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date/time format '{}'", format));
+    }
+    Ok(Local::now().format(format).to_string())
+}
+
+struct Expander<'a> {
+    args: &'a HashMap<String, String>,
+    lookup: SnippetLookup<'a>,
+    clipboard: Option<String>,
+    stack: Vec<String>,
+    text: String,
+    cursor: Option<usize>,
+}
+
+impl Expander<'_> {
+    fn clipboard(&mut self) -> Result<String, String> {
+        if self.clipboard.is_none() {
+            let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+            self.clipboard = Some(clipboard.get_text().unwrap_or_default());
+        }
+        Ok(self.clipboard.clone().unwrap_or_default())
+    }
+
+    fn expand(&mut self, template: &str) -> Result<(), String> {
+        for segment in parse(template) {
+            match segment {
+                Segment::Text(text) => self.text.push_str(&text),
+                Segment::Date(format) => self
+                    .text
+                    .push_str(&format_now(format.as_deref().unwrap_or("%Y-%m-%d"))?),
+                Segment::Time(format) => self
+                    .text
+                    .push_str(&format_now(format.as_deref().unwrap_or("%H:%M"))?),
+                Segment::DateTime(format) => self
+                    .text
+                    .push_str(&format_now(format.as_deref().unwrap_or("%Y-%m-%d %H:%M"))?),
+                Segment::Clipboard => {
+                    let clipboard = self.clipboard()?;
+                    self.text.push_str(&clipboard);
+                }
+                Segment::Uuid => self.text.push_str(&uuid::Uuid::new_v4().to_string()),
+                Segment::Cursor => {
+                    // Only the first cursor counts, including ones from includes
+                    if self.cursor.is_none() {
+                        self.cursor = Some(self.text.chars().count());
+                    }
+                }
+                Segment::Argument(argument) => {
+                    let value = self
+                        .args
+                        .get(&argument.name)
+                        .or(argument.default.as_ref())
+                        .ok_or_else(|| format!("Missing argument '{}'", argument.name))?;
+                    self.text.push_str(value);
+                }
+                Segment::Include(name) => {
+                    if self.stack.contains(&name) {
+                        return Err(format!("Snippet '{}' includes itself", name));
+                    }
+                    if self.stack.len() >= MAX_INCLUDE_DEPTH {
+                        return Err("Snippet includes are nested too deeply".into());
+                    }
+                    let included = (self.lookup)(&name)?;
+                    self.stack.push(name);
+                    self.expand(&included)?;
+                    self.stack.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands every placeholder in `template`.
+///
+/// `args` supplies `{argument}` values by name; `lookup` resolves
+/// `{snippet}` includes, which are expanded with the same arguments.
+pub fn expand(
+    template: &str,
+    args: &HashMap<String, String>,
+    lookup: SnippetLookup,
+) -> Result<Expansion, String> {
+    let mut expander = Expander {
+        args,
+        lookup,
+        clipboard: None,
+        stack: Vec::new(),
+        text: String::new(),
+        cursor: None,
+    };
+    expander.expand(template)?;
+    Ok(Expansion {
+        text: expander.text,
+        cursor: expander.cursor,
+    })
+}
+
+fn collect_arguments(
+    template: &str,
+    lookup: SnippetLookup,
+    stack: &mut Vec<String>,
+    found: &mut Vec<Argument>,
+) -> Result<(), String> {
+    for segment in parse(template) {
+        match segment {
+            Segment::Argument(argument) if !found.iter().any(|a| a.name == argument.name) => {
+                found.push(argument);
+            }
+            Segment::Include(name) => {
+                if stack.contains(&name) || stack.len() >= MAX_INCLUDE_DEPTH {
+                    continue;
+                }
+                let included = lookup(&name)?;
+                stack.push(name);
+                collect_arguments(&included, lookup, stack, found)?;
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The distinct `{argument}` prompts in `template` and its includes, in order.
+pub fn arguments(template: &str, lookup: SnippetLookup) -> Result<Vec<Argument>, String> {
+    let mut found = Vec::new();
+    collect_arguments(template, lookup, &mut Vec::new(), &mut found)?;
+    Ok(found)
+}
+
+/// `template` with each `{snippet}` include of a name in `renamed` pointed at
+/// its new name; everything else is kept byte for byte.
+pub fn rename_includes(template: &str, renamed: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(pos) = rest.find('{') {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if let Some(after) = tail.strip_prefix("{{") {
+            out.push_str("{{");
+            rest = after;
+            continue;
+        }
+        match placeholder_end(tail) {
+            Some(close) => {
+                match parse_placeholder(&tail[1..close]) {
+                    Some(Segment::Include(name)) if renamed.contains_key(&name) => {
+                        out.push_str(&format!("{{snippet name=\"{}\"}}", renamed[&name]));
+                    }
+                    _ => out.push_str(&tail[..close + 1]),
+                }
+                rest = &tail[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippets(name: &str) -> Result<String, String> {
+        match name {
+            "signature" => Ok("Regards, {argument name=\"Sender\"}".into()),
+            "greeting" => Ok("Hi {argument name=\"Customer\"}{cursor}, ".into()),
+            "loop" => Ok("again {snippet name=\"loop\"}".into()),
+            "ping" => Ok("{snippet name=\"pong\"}".into()),
+            "pong" => Ok("{snippet name=\"ping\"}".into()),
+            _ => Err(format!("Snippet '{}' not found", name)),
+        }
+    }
+
+    fn expand_with(template: &str, args: &[(&str, &str)]) -> Result<Expansion, String> {
+        let args = args
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        expand(template, &args, &snippets)
+    }
+
+    fn argument(name: &str, default: Option<&str>) -> Argument {
+        Argument {
+            name: name.into(),
+            default: default.map(String::from),
+        }
+    }
+
+    #[test]
+    fn parses_every_placeholder_kind() {
+        let segments = parse(
+            "{date}{date:%d/%m/%Y}{time:%H:%M:%S}{datetime}{clipboard}{uuid}{cursor}\
+             {argument name=\"Customer\" default=\"ACME\"}{snippet name=\"signature\"}",
+        );
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Date(None),
+                Segment::Date(Some("%d/%m/%Y".into())),
+                Segment::Time(Some("%H:%M:%S".into())),
+                Segment::DateTime(None),
+                Segment::Clipboard,
+                Segment::Uuid,
+                Segment::Cursor,
+                Segment::Argument(argument("Customer", Some("ACME"))),
+                Segment::Include("signature".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_or_malformed_placeholders_untouched() {
+        for template in [
+            "{date foo}",
+            "{uuid now}",
+            "{argument}",
+            "{argument name=\"a\" colour=\"red\"}",
+            "{nope}",
+            "{ \"json\": 1 }",
+            "{unclosed",
+        ] {
+            assert_eq!(parse(template), vec![Segment::Text(template.into())]);
+        }
+    }
+
+    #[test]
+    fn braces_inside_quoted_attributes_stay_in_the_value() {
+        assert_eq!(
+            parse("{argument name=\"x\" default=\"a}b{c\"}!"),
+            vec![
+                Segment::Argument(argument("x", Some("a}b{c"))),
+                Segment::Text("!".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let expansion = expand_with("{{uuid}} {{", &[]).unwrap();
+        assert_eq!(expansion.text, "{uuid} {");
+    }
+
+    #[test]
+    fn expands_dates_times_and_uuids() {
+        let year = Local::now().format("%Y").to_string();
+        let text = expand_with("{date:%Y}", &[]).unwrap().text;
+        assert_eq!(text, year);
+        assert_eq!(expand_with("{date}", &[]).unwrap().text.len(), 10);
+        assert_eq!(expand_with("{time}", &[]).unwrap().text.len(), 5);
+        let uuid = expand_with("{uuid}", &[]).unwrap().text;
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+        assert!(expand_with("{date:%Q}", &[])
+            .unwrap_err()
+            .contains("Invalid date/time format"));
+    }
+
+    #[test]
+    fn arguments_fall_back_to_their_default() {
+        let template = "Dear {argument name=\"Customer\" default=\"ACME\"}";
+        assert_eq!(expand_with(template, &[]).unwrap().text, "Dear ACME");
+        assert_eq!(
+            expand_with(template, &[("Customer", "Initech")])
+                .unwrap()
+                .text,
+            "Dear Initech"
+        );
+        assert_eq!(
+            expand_with("{argument name=\"Order\"}", &[]).unwrap_err(),
+            "Missing argument 'Order'"
+        );
+    }
+
+    #[test]
+    fn includes_share_arguments_and_the_first_cursor() {
+        let expansion = expand_with(
+            "{snippet name=\"greeting\"}{cursor}{snippet name=\"signature\"}",
+            &[("Customer", "Ann"), ("Sender", "Bob")],
+        )
+        .unwrap();
+        assert_eq!(expansion.text, "Hi Ann, Regards, Bob");
+        assert_eq!(expansion.cursor, Some(6));
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        assert_eq!(
+            expand_with("{snippet name=\"loop\"}", &[]).unwrap_err(),
+            "Snippet 'loop' includes itself"
+        );
+        assert_eq!(
+            expand_with("{snippet name=\"ping\"}", &[]).unwrap_err(),
+            "Snippet 'ping' includes itself"
+        );
+        assert!(arguments("{snippet name=\"loop\"}", &snippets)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn lists_distinct_arguments_through_includes() {
+        let found = arguments(
+            "{argument name=\"Customer\" default=\"ACME\"}\
+             {snippet name=\"greeting\"}{snippet name=\"signature\"}",
+            &snippets,
+        )
+        .unwrap();
+        assert_eq!(
+            found,
+            vec![argument("Customer", Some("ACME")), argument("Sender", None)]
+        );
+        assert!(arguments("{snippet name=\"missing\"}", &snippets).is_err());
+    }
+
+    #[test]
+    fn renames_only_matching_includes() {
+        let renamed = HashMap::from([("sig".to_string(), "sig-2".to_string())]);
+        assert_eq!(
+            rename_includes(
+                "{{snippet name=\"sig\"}} {snippet name=sig} {snippet name=\"other\"}{date:%H}",
+                &renamed
+            ),
+            "{{snippet name=\"sig\"}} {snippet name=\"sig-2\"} {snippet name=\"other\"}{date:%H}"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+// OS backends behind one trait per capability. The implementations for the
+// running OS are picked once, at startup, and share a `CommandRunner`; `install`
+// swaps in a `Platform` built on a fake runner to check the commands issued.
+
+use crate::appsLib;
+use crate::audioDevicesLib::AudioDevices;
+use crate::commandLib::{CommandRunner, SystemRunner};
+use crate::mediaLib::VolumeState;
+#[cfg(not(target_os = "linux"))]
+use crate::systemManagementLib;
+use crate::AppInfo;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+
+/// Volume of the default output device.
+pub trait VolumeControl: Send + Sync {
+    fn volume(&self) -> Result<VolumeState, String>;
+    fn set_volume(&self, percent: u8) -> Result<(), String>;
+    fn set_muted(&self, muted: bool) -> Result<(), String>;
+}
+
+/// Brightness of the built-in display, in percent.
+pub trait BrightnessControl: Send + Sync {
+    fn brightness(&self) -> Result<u8, String>;
+    fn set_brightness(&self, percent: u8) -> Result<(), String>;
+}
+
+pub trait PowerControl: Send + Sync {
+    fn shutdown(&self) -> Result<(), String>;
+    fn restart(&self) -> Result<(), String>;
+    fn lock(&self) -> Result<(), String>;
+    fn suspend(&self) -> Result<(), String>;
+    fn hibernate(&self) -> Result<(), String>;
+    fn log_out(&self) -> Result<(), String>;
+}
+
+pub trait AppLauncher: Send + Sync {
+    fn installed_apps(&self) -> Vec<AppInfo>;
+    /// Starts an app given its name or the path from `installed_apps`.
+    fn launch(&self, app: &str) -> Result<(), String>;
+    /// Opens a URL in the default browser.
+    fn open_url(&self, url: &str) -> Result<(), String>;
+}
+
+pub struct Platform {
+    pub runner: Arc<dyn CommandRunner>,
+    pub volume: Arc<dyn VolumeControl>,
+    pub audio_devices: Arc<dyn AudioDevices>,
+    pub brightness: Arc<dyn BrightnessControl>,
+    pub power: Arc<dyn PowerControl>,
+    pub apps: Arc<dyn AppLauncher>,
+}
+
+impl Platform {
+    /// The backends for the OS this was built for.
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        #[cfg(target_os = "macos")]
+        let audio = Arc::new(crate::audioDevicesLib::MacAudio::new(runner.clone()));
+        #[cfg(target_os = "windows")]
+        let audio = Arc::new(crate::audioDevicesLib::WindowsAudio::new(runner.clone()));
+        #[cfg(target_os = "linux")]
+        let audio = Arc::new(crate::pulseAudioLib::PulseAudio::new(runner.clone()));
+
+        #[cfg(not(target_os = "linux"))]
+        let brightness = Arc::new(systemManagementLib::SystemBrightness::new(runner.clone()));
+        #[cfg(target_os = "linux")]
+        let brightness = Arc::new(crate::backlightLib::Backlight::system());
+
+        #[cfg(not(target_os = "linux"))]
+        let power = Arc::new(systemManagementLib::SystemPower::new(runner.clone()));
+        #[cfg(target_os = "linux")]
+        let power = Arc::new(crate::logindLib::Logind::system());
+
+        Platform {
+            volume: audio.clone(),
+            audio_devices: audio,
+            brightness,
+            power,
+            apps: Arc::new(appsLib::SystemApps::new(runner.clone())),
+            runner,
+        }
+    }
+}
+
+static PLATFORM: Lazy<RwLock<Arc<Platform>>> =
+    Lazy::new(|| RwLock::new(Arc::new(Platform::new(Arc::new(SystemRunner)))));
+
+pub fn init() {
+    Lazy::force(&PLATFORM);
+}
+
+pub fn current() -> Arc<Platform> {
+    PLATFORM.read().unwrap().clone()
+}
+
+// Tests run in parallel; only one at a time may swap the platform
+#[cfg(test)]
+static INSTALL_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Puts the previous platform back when dropped.
+#[cfg(test)]
+pub struct Installed {
+    previous: Arc<Platform>,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl Drop for Installed {
+    fn drop(&mut self) {
+        *PLATFORM.write().unwrap() = self.previous.clone();
+    }
+}
+
+/// Makes `platform` the current one for as long as the returned guard lives.
+/// Other tests calling `install` wait until then, so a test that reaches
+/// `current()` should install the platform it expects.
+#[cfg(test)]
+pub fn install(platform: Platform) -> Installed {
+    let lock = INSTALL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = std::mem::replace(&mut *PLATFORM.write().unwrap(), Arc::new(platform));
+    Installed {
+        previous,
+        _lock: lock,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commandLib::FakeRunner;
+
+    #[test]
+    fn installed_platform_runs_commands_through_its_runner() {
+        let runner = Arc::new(FakeRunner::default());
+        let _installed = install(Platform::new(runner.clone()));
+
+        #[cfg(target_os = "linux")]
+        {
+            current().volume.set_volume(30).unwrap();
+            let dir = std::env::temp_dir();
+            crate::fileActionsLib::reveal_in_file_manager(dir.to_str().unwrap()).unwrap();
+            let items = format!(
+                "array:string:file://{}",
+                crate::trashLib::encode_path(&std::path::absolute(&dir).unwrap())
+            );
+            assert_eq!(
+                runner.calls(),
+                vec![
+                    vec!["pactl", "info"],
+                    vec![
+                        "env",
+                        "LC_ALL=C",
+                        "pactl",
+                        "set-sink-volume",
+                        "@DEFAULT_SINK@",
+                        "30%"
+                    ],
+                    vec![
+                        "dbus-send",
+                        "--session",
+                        "--print-reply",
+                        "--dest=org.freedesktop.FileManager1",
+                        "--type=method_call",
+                        "/org/freedesktop/FileManager1",
+                        "org.freedesktop.FileManager1.ShowItems",
+                        &items,
+                        "string:",
+                    ],
+                ]
+            );
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            current().volume.set_volume(30).unwrap();
+            current().power.shutdown().unwrap();
+            assert_eq!(
+                runner.calls(),
+                vec![
+                    vec!["osascript", "-e", "set volume output volume 30"],
+                    vec!["osascript", "-e", "tell app \"System Events\" to shut down"],
+                ]
+            );
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            current().power.shutdown().unwrap();
+            current().power.restart().unwrap();
+            assert_eq!(
+                runner.calls(),
+                vec![
+                    vec!["shutdown", "/s", "/t", "0"],
+                    vec!["shutdown", "/r", "/t", "0"],
+                ]
+            );
+        }
+    }
+}
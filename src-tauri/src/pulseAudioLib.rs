@@ -0,0 +1,577 @@
+// Volume and device control for PulseAudio and PipeWire through their command-line
+// tools. `pactl` works with both (PipeWire ships a Pulse server); `wpctl` is
+// the fallback on PipeWire systems without it.
+
+use crate::audioDevicesLib::{AudioDevice, AudioDevices, DeviceKind};
+use crate::commandLib::CommandRunner;
+use crate::mediaLib::VolumeState;
+use crate::platformLib::VolumeControl;
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tool {
+    Pactl,
+    Wpctl,
+}
+
+pub struct PulseAudio {
+    runner: Arc<dyn CommandRunner>,
+    tool: OnceCell<Tool>,
+}
+
+/// Reads the percentage from `pactl get-sink-volume` output, averaging the
+/// channels, e.g. "Volume: front-left: 32768 /  50% / -18.06 dB, ..."
+pub fn parse_pactl_volume(output: &str) -> Option<u8> {
+    let percents: Vec<u32> = output
+        .split('/')
+        .filter_map(|part| part.trim().strip_suffix('%')?.trim().parse().ok())
+        .collect();
+    if percents.is_empty() {
+        return None;
+    }
+    let average = percents.iter().sum::<u32>() / percents.len() as u32;
+    Some(average.min(u8::MAX as u32) as u8)
+}
+
+/// Reads `pactl get-sink-mute` output: "Mute: yes" or "Mute: no".
+pub fn parse_pactl_mute(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("Mute:")?.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads `wpctl get-volume` output, e.g. "Volume: 0.50" or "Volume: 0.50 [MUTED]".
+pub fn parse_wpctl_volume(output: &str) -> Option<(u8, bool)> {
+    let rest = output.trim().strip_prefix("Volume:")?.trim();
+    let level: f64 = rest.split_whitespace().next()?.parse().ok()?;
+    let percent = (level * 100.0).round().clamp(0.0, u8::MAX as f64) as u8;
+    Some((percent, rest.contains("[MUTED]")))
+}
+
+/// Reads the default sink and source names from `pactl info`.
+pub fn parse_pactl_defaults(output: &str) -> (Option<String>, Option<String>) {
+    let field = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(name))
+            .map(|value| value.trim().to_string())
+    };
+    (field("Default Sink:"), field("Default Source:"))
+}
+
+/// Reads `pactl list sinks` or `pactl list sources`. Monitor sources, which
+/// mirror an output, are left out.
+pub fn parse_pactl_devices(
+    output: &str,
+    kind: DeviceKind,
+    default: Option<&str>,
+) -> Vec<AudioDevice> {
+    let mut devices = Vec::new();
+    let mut current: Option<AudioDevice> = None;
+    for line in output.lines() {
+        // Each device starts with an unindented "Sink #0" or "Source #1"
+        if !line.starts_with(char::is_whitespace) {
+            devices.extend(current.take());
+            if !line.trim().is_empty() {
+                current = Some(AudioDevice {
+                    id: String::new(),
+                    name: String::new(),
+                    kind,
+                    is_default: false,
+                    volume: None,
+                    muted: None,
+                });
+            }
+            continue;
+        }
+        let Some(device) = current.as_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("Name:") {
+            device.id = name.trim().to_string();
+            device.is_default = default == Some(device.id.as_str());
+        } else if let Some(description) = line.strip_prefix("Description:") {
+            device.name = description.trim().to_string();
+        } else if line.starts_with("Mute:") {
+            device.muted = parse_pactl_mute(line);
+        } else if line.starts_with("Volume:") {
+            device.volume = parse_pactl_volume(line);
+        }
+    }
+    devices.extend(current);
+    devices.retain(|device| !device.id.is_empty() && !device.id.ends_with(".monitor"));
+    for device in &mut devices {
+        if device.name.is_empty() {
+            device.name = device.id.clone();
+        }
+    }
+    devices
+}
+
+/// Reads the audio sinks and sources from `wpctl status`, e.g.
+/// " │  *   46. Built-in Audio Analog Stereo        [vol: 0.40 MUTED]"
+pub fn parse_wpctl_status(output: &str) -> Vec<AudioDevice> {
+    let mut devices = Vec::new();
+    let mut in_audio = false;
+    let mut kind = None;
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+            // Top-level sections: "Audio", "Video", "Settings"
+            in_audio = line.trim() == "Audio";
+            kind = None;
+            continue;
+        }
+        let entry = line.trim_start_matches(|c: char| c.is_whitespace() || "│├└─".contains(c));
+        if entry.ends_with(':') {
+            kind = match entry {
+                "Sinks:" => Some(DeviceKind::Output),
+                "Sources:" => Some(DeviceKind::Input),
+                _ => None,
+            };
+            continue;
+        }
+        let Some(kind) = kind.filter(|_| in_audio) else {
+            continue;
+        };
+        let is_default = entry.starts_with('*');
+        let entry = entry.trim_start_matches('*').trim_start();
+        let Some((id, rest)) = entry.split_once(". ") else {
+            continue;
+        };
+        if id.parse::<u32>().is_err() {
+            continue;
+        }
+        let (name, details) = rest.split_once('[').unwrap_or((rest, ""));
+        let (volume, muted) = match details.trim_end_matches(']').strip_prefix("vol:") {
+            Some(volume) => {
+                let level = volume
+                    .split_whitespace()
+                    .next()
+                    .and_then(|v| v.parse::<f64>().ok());
+                (
+                    level.map(|level| (level * 100.0).round().clamp(0.0, u8::MAX as f64) as u8),
+                    Some(volume.contains("MUTED")),
+                )
+            }
+            None => (None, None),
+        };
+        devices.push(AudioDevice {
+            id: id.to_string(),
+            name: name.trim().to_string(),
+            kind,
+            is_default,
+            volume,
+            muted,
+        });
+    }
+    devices
+}
+
+impl PulseAudio {
+    /// Picks `pactl` or `wpctl` on first use.
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        PulseAudio {
+            runner,
+            tool: OnceCell::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_tool(runner: Arc<dyn CommandRunner>, tool: Tool) -> Self {
+        PulseAudio {
+            runner,
+            tool: OnceCell::with_value(tool),
+        }
+    }
+
+    // `pactl` when a Pulse server answers, else `wpctl`. A failed probe isn't
+    // remembered, so a server started later is still found.
+    fn tool(&self) -> Result<Tool, String> {
+        self.tool
+            .get_or_try_init(|| {
+                let works =
+                    |program: &str, args: &[&str]| self.runner.output(program, args).is_ok();
+                if works("pactl", &["info"]) {
+                    Ok(Tool::Pactl)
+                } else if works("wpctl", &["status"]) {
+                    Ok(Tool::Wpctl)
+                } else {
+                    Err("Neither pactl nor wpctl can reach an audio server".to_string())
+                }
+            })
+            .copied()
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        match self.tool()? {
+            // pactl translates its output; the parsers expect English
+            Tool::Pactl => {
+                let mut full = vec!["LC_ALL=C", "pactl"];
+                full.extend_from_slice(args);
+                self.runner.output("env", &full)
+            }
+            Tool::Wpctl => self.runner.output("wpctl", args),
+        }
+    }
+}
+
+impl VolumeControl for PulseAudio {
+    /// Default output volume; the level may exceed 100 when over-amplified.
+    fn volume(&self) -> Result<VolumeState, String> {
+        let (level, muted) = match self.tool()? {
+            Tool::Pactl => (
+                parse_pactl_volume(&self.run(&["get-sink-volume", "@DEFAULT_SINK@"])?),
+                parse_pactl_mute(&self.run(&["get-sink-mute", "@DEFAULT_SINK@"])?),
+            ),
+            Tool::Wpctl => {
+                let parsed =
+                    parse_wpctl_volume(&self.run(&["get-volume", "@DEFAULT_AUDIO_SINK@"])?);
+                (
+                    parsed.map(|(level, _)| level),
+                    parsed.map(|(_, muted)| muted),
+                )
+            }
+        };
+        Ok(VolumeState {
+            level: level.ok_or("Unexpected volume output")?,
+            muted: muted.ok_or("Unexpected mute output")?,
+        })
+    }
+
+    fn set_volume(&self, percent: u8) -> Result<(), String> {
+        match self.tool()? {
+            Tool::Pactl => {
+                let value = format!("{}%", percent);
+                self.run(&["set-sink-volume", "@DEFAULT_SINK@", &value])
+            }
+            Tool::Wpctl => {
+                let value = format!("{:.2}", percent as f64 / 100.0);
+                self.run(&["set-volume", "@DEFAULT_AUDIO_SINK@", &value])
+            }
+        }
+        .map(|_| ())
+    }
+
+    fn set_muted(&self, muted: bool) -> Result<(), String> {
+        let value = if muted { "1" } else { "0" };
+        match self.tool()? {
+            Tool::Pactl => self.run(&["set-sink-mute", "@DEFAULT_SINK@", value]),
+            Tool::Wpctl => self.run(&["set-mute", "@DEFAULT_AUDIO_SINK@", value]),
+        }
+        .map(|_| ())
+    }
+}
+
+fn pactl_type(kind: DeviceKind) -> &'static str {
+    match kind {
+        DeviceKind::Output => "sink",
+        DeviceKind::Input => "source",
+    }
+}
+
+impl AudioDevices for PulseAudio {
+    fn list_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        match self.tool()? {
+            Tool::Pactl => {
+                let (sink, source) = parse_pactl_defaults(&self.run(&["info"])?);
+                let mut devices = parse_pactl_devices(
+                    &self.run(&["list", "sinks"])?,
+                    DeviceKind::Output,
+                    sink.as_deref(),
+                );
+                devices.extend(parse_pactl_devices(
+                    &self.run(&["list", "sources"])?,
+                    DeviceKind::Input,
+                    source.as_deref(),
+                ));
+                Ok(devices)
+            }
+            Tool::Wpctl => Ok(parse_wpctl_status(&self.run(&["status"])?)),
+        }
+    }
+
+    fn set_default_device(&self, kind: DeviceKind, id: &str) -> Result<(), String> {
+        match self.tool()? {
+            Tool::Pactl => self.run(&[&format!("set-default-{}", pactl_type(kind)), id]),
+            // wpctl ids are unique across sinks and sources
+            Tool::Wpctl => self.run(&["set-default", id]),
+        }
+        .map(|_| ())
+    }
+
+    fn set_device_volume(&self, kind: DeviceKind, id: &str, percent: u8) -> Result<(), String> {
+        match self.tool()? {
+            Tool::Pactl => {
+                let value = format!("{}%", percent);
+                self.run(&[&format!("set-{}-volume", pactl_type(kind)), id, &value])
+            }
+            Tool::Wpctl => {
+                let value = format!("{:.2}", percent as f64 / 100.0);
+                self.run(&["set-volume", id, &value])
+            }
+        }
+        .map(|_| ())
+    }
+
+    fn set_device_muted(&self, kind: DeviceKind, id: &str, muted: bool) -> Result<(), String> {
+        let value = if muted { "1" } else { "0" };
+        match self.tool()? {
+            Tool::Pactl => self.run(&[&format!("set-{}-mute", pactl_type(kind)), id, value]),
+            Tool::Wpctl => self.run(&["set-mute", id, value]),
+        }
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commandLib::FakeRunner;
+
+    const PACTL_SINKS: &str = "Sink #0
+\tState: SUSPENDED
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo
+\tDescription: Built-in Audio Analog Stereo
+\tDriver: PipeWire
+\tMute: no
+\tVolume: front-left: 32768 /  50% / -18.06 dB,   front-right: 32768 /  50% / -18.06 dB
+\t        balance 0.00
+\tBase Volume: 65536 / 100% / 0.00 dB
+
+Sink #1
+\tState: RUNNING
+\tName: bluez_output.00_1B_66_01_02_03.1
+\tDescription: Headphones
+\tMute: yes
+\tVolume: front-left: 65536 / 100% / 0.00 dB,   front-right: 32768 /  50% / -18.06 dB
+";
+
+    const PACTL_SOURCES: &str = "Source #2
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+\tDescription: Monitor of Built-in Audio Analog Stereo
+\tMute: no
+\tVolume: front-left: 65536 / 100% / 0.00 dB
+
+Source #3
+\tName: alsa_input.pci-0000_00_1f.3.analog-stereo
+\tMute: no
+\tVolume: mono: 49152 /  75% / -7.50 dB
+";
+
+    const WPCTL_STATUS: &str = "PipeWire 'pipewire-0' [1.0.5, user@host, cookie:1234]
+ └─ Clients:
+        33. pipewire                            [1.0.5, user@host, pid:1017]
+
+Audio
+ ├─ Devices:
+ │      42. Built-in Audio                      [alsa]
+ │
+ ├─ Sinks:
+ │  *   46. Built-in Audio Analog Stereo        [vol: 0.40 MUTED]
+ │      50. HDMI Output                         [vol: 1.00]
+ │
+ ├─ Sink endpoints:
+ │
+ ├─ Sources:
+ │  *   47. Built-in Audio Analog Stereo        [vol: 0.75]
+ │
+ ├─ Source endpoints:
+ │
+ └─ Streams:
+        60. Firefox
+             61. output_FL       > Built-in Audio:playback_FL\t[active]
+
+Video
+ ├─ Devices:
+ │      55. Integrated Camera                   [v4l2]
+ ├─ Sources:
+ │  *   58. Integrated Camera (V4L2)
+";
+
+    fn device(id: &str, name: &str, kind: DeviceKind, is_default: bool) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind,
+            is_default,
+            volume: None,
+            muted: None,
+        }
+    }
+
+    #[test]
+    fn pactl_volume_averages_channels() {
+        let runner = Arc::new(FakeRunner::default());
+        runner
+            .respond(
+                "env LC_ALL=C pactl get-sink-volume @DEFAULT_SINK@",
+                "Volume: front-left: 65536 / 100% / 0.00 dB,   front-right: 32768 /  50% / -18.06 dB\n        balance -0.50",
+            )
+            .respond("env LC_ALL=C pactl get-sink-mute @DEFAULT_SINK@", "Mute: yes");
+        let pulse = PulseAudio::with_tool(runner.clone(), Tool::Pactl);
+
+        assert_eq!(
+            pulse.volume().unwrap(),
+            VolumeState {
+                level: 75,
+                muted: true
+            }
+        );
+    }
+
+    #[test]
+    fn pactl_volume_rejects_unexpected_output() {
+        let runner = Arc::new(FakeRunner::default());
+        runner
+            .respond(
+                "env LC_ALL=C pactl get-sink-volume @DEFAULT_SINK@",
+                "No such entity",
+            )
+            .respond(
+                "env LC_ALL=C pactl get-sink-mute @DEFAULT_SINK@",
+                "Mute: no",
+            );
+        let pulse = PulseAudio::with_tool(runner, Tool::Pactl);
+
+        assert!(pulse.volume().is_err());
+    }
+
+    #[test]
+    fn wpctl_volume_reads_level_and_mute() {
+        let runner = Arc::new(FakeRunner::default());
+        runner.respond(
+            "wpctl get-volume @DEFAULT_AUDIO_SINK@",
+            "Volume: 0.42 [MUTED]",
+        );
+        let pulse = PulseAudio::with_tool(runner.clone(), Tool::Wpctl);
+
+        assert_eq!(
+            pulse.volume().unwrap(),
+            VolumeState {
+                level: 42,
+                muted: true
+            }
+        );
+
+        pulse.set_volume(7).unwrap();
+        assert_eq!(
+            runner.calls().last().unwrap(),
+            &["wpctl", "set-volume", "@DEFAULT_AUDIO_SINK@", "0.07"]
+        );
+    }
+
+    #[test]
+    fn pactl_lists_sinks_and_sources_without_monitors() {
+        let runner = Arc::new(FakeRunner::default());
+        runner
+            .respond(
+                "env LC_ALL=C pactl info",
+                "Server Name: PulseAudio (on PipeWire 1.0.5)\n\
+                 Default Sink: bluez_output.00_1B_66_01_02_03.1\n\
+                 Default Source: alsa_input.pci-0000_00_1f.3.analog-stereo",
+            )
+            .respond("env LC_ALL=C pactl list sinks", PACTL_SINKS)
+            .respond("env LC_ALL=C pactl list sources", PACTL_SOURCES);
+        let pulse = PulseAudio::with_tool(runner, Tool::Pactl);
+
+        let speakers = AudioDevice {
+            volume: Some(50),
+            muted: Some(false),
+            ..device(
+                "alsa_output.pci-0000_00_1f.3.analog-stereo",
+                "Built-in Audio Analog Stereo",
+                DeviceKind::Output,
+                false,
+            )
+        };
+        let headphones = AudioDevice {
+            volume: Some(75),
+            muted: Some(true),
+            ..device(
+                "bluez_output.00_1B_66_01_02_03.1",
+                "Headphones",
+                DeviceKind::Output,
+                true,
+            )
+        };
+        // No description, so the name stands in
+        let microphone = AudioDevice {
+            volume: Some(75),
+            muted: Some(false),
+            ..device(
+                "alsa_input.pci-0000_00_1f.3.analog-stereo",
+                "alsa_input.pci-0000_00_1f.3.analog-stereo",
+                DeviceKind::Input,
+                true,
+            )
+        };
+        assert_eq!(
+            pulse.list_devices().unwrap(),
+            vec![speakers, headphones, microphone]
+        );
+    }
+
+    #[test]
+    fn wpctl_lists_audio_sinks_and_sources_only() {
+        let runner = Arc::new(FakeRunner::default());
+        runner.respond("wpctl status", WPCTL_STATUS);
+        let pulse = PulseAudio::with_tool(runner.clone(), Tool::Wpctl);
+
+        let speakers = AudioDevice {
+            volume: Some(40),
+            muted: Some(true),
+            ..device(
+                "46",
+                "Built-in Audio Analog Stereo",
+                DeviceKind::Output,
+                true,
+            )
+        };
+        let hdmi = AudioDevice {
+            volume: Some(100),
+            muted: Some(false),
+            ..device("50", "HDMI Output", DeviceKind::Output, false)
+        };
+        let microphone = AudioDevice {
+            volume: Some(75),
+            muted: Some(false),
+            ..device(
+                "47",
+                "Built-in Audio Analog Stereo",
+                DeviceKind::Input,
+                true,
+            )
+        };
+        assert_eq!(
+            pulse.list_devices().unwrap(),
+            vec![speakers, hdmi, microphone]
+        );
+
+        pulse.set_default_device(DeviceKind::Input, "47").unwrap();
+        assert_eq!(
+            runner.calls().last().unwrap(),
+            &["wpctl", "set-default", "47"]
+        );
+    }
+
+    #[test]
+    fn probes_pactl_before_wpctl() {
+        let runner = Arc::new(FakeRunner::default());
+        runner.fail("pactl info", "Connection failure: Connection refused");
+        let pulse = PulseAudio::new(runner.clone());
+
+        pulse.set_muted(true).unwrap();
+        assert_eq!(
+            runner.calls(),
+            vec![
+                vec!["pactl", "info"],
+                vec!["wpctl", "status"],
+                vec!["wpctl", "set-mute", "@DEFAULT_AUDIO_SINK@", "1"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+#[cfg(not(target_os = "linux"))]
+use crate::platformLib;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RecentFile {
+    pub path: String,
+    pub name: String,
+    // RFC 3339 timestamp of the last time the file was opened
+    pub last_used: String,
+    // Applications that opened the file, when the platform records them
+    pub applications: Vec<String>,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn xbel_path() -> Option<PathBuf> {
+    crate::xdgLib::data_home().map(|dir| dir.join("recently-used.xbel"))
+}
+
+/// Parses a freedesktop `recently-used.xbel` bookmark file.
+///
+/// Only local `file://` entries are returned; their `last_used` is the most
+/// recent of the bookmark's and its applications' timestamps.
+pub fn parse_xbel(xml: &str) -> Vec<RecentFile> {
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::Reader;
+
+    fn attr(e: &BytesStart, name: &str) -> Option<String> {
+        e.try_get_attribute(name)
+            .ok()
+            .flatten()
+            .and_then(|a| a.unescape_value().ok())
+            .map(|v| v.into_owned())
+    }
+
+    fn timestamp(value: Option<String>) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&value?)
+            .ok()
+            .map(|t| t.with_timezone(&Local))
+    }
+
+    type Bookmark = (PathBuf, Option<DateTime<Local>>, Vec<String>);
+
+    fn finish(bookmark: Option<Bookmark>, files: &mut Vec<RecentFile>) {
+        if let Some((path, Some(last_used), applications)) = bookmark {
+            files.push(RecentFile {
+                name: file_name(&path),
+                path: path.display().to_string(),
+                last_used: last_used.to_rfc3339(),
+                applications,
+            });
+        }
+    }
+
+    let mut reader = Reader::from_str(xml);
+    let mut files = Vec::new();
+    let mut current: Option<Bookmark> = None;
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(event) => event,
+        };
+        // A self-closing `<bookmark/>` has no children and no end tag
+        let empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"bookmark" => {
+                    let path = attr(&e, "href")
+                        .and_then(|href| href.strip_prefix("file://").map(String::from))
+                        .and_then(|p| urlencoding::decode(&p).ok().map(|p| PathBuf::from(&*p)));
+                    current = path.map(|path| {
+                        let last_used = ["visited", "modified", "added"]
+                            .into_iter()
+                            .filter_map(|name| timestamp(attr(&e, name)))
+                            .max();
+                        (path, last_used, Vec::new())
+                    });
+                    if empty {
+                        finish(current.take(), &mut files);
+                    }
+                }
+                b"bookmark:application" => {
+                    if let Some((_, last_used, applications)) = current.as_mut() {
+                        if let Some(name) = attr(&e, "name") {
+                            applications.push(name);
+                        }
+                        if let Some(modified) = timestamp(attr(&e, "modified")) {
+                            *last_used = (*last_used).max(Some(modified));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::End(e) if e.name().as_ref() == b"bookmark" => {
+                finish(current.take(), &mut files);
+            }
+            _ => {}
+        }
+    }
+
+    files
+}
+
+// Paths passed to each `mdls` call, keeping its command line short
+#[cfg(target_os = "macos")]
+const MDLS_BATCH: usize = 200;
+
+// `mdls -raw` prints one value per file, separated by NULs, with `(null)`
+// for files that were never opened
+#[cfg(any(target_os = "macos", test))]
+fn parse_mdls_dates(output: &str) -> Vec<Option<DateTime<Local>>> {
+    output
+        .split('\0')
+        .map(|value| {
+            DateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M:%S %z")
+                .ok()
+                .map(|t| t.with_timezone(&Local))
+        })
+        .collect()
+}
+
+fn recent_files() -> Vec<RecentFile> {
+    #[cfg(target_os = "linux")]
+    {
+        xbel_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|xml| parse_xbel(&xml))
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // Spotlight tracks when files were last opened, but not by which app
+        let runner = platformLib::current().runner.clone();
+        let Ok(output) = runner.output("mdfind", &["kMDItemLastUsedDate >= $time.today(-30)"])
+        else {
+            return Vec::new();
+        };
+        let paths: Vec<&str> = output.lines().collect();
+        let mut files = Vec::new();
+        for chunk in paths.chunks(MDLS_BATCH) {
+            let args: Vec<&str> = ["-raw", "-name", "kMDItemLastUsedDate", "--"]
+                .into_iter()
+                .chain(chunk.iter().copied())
+                .collect();
+            let Ok(dates) = runner.output("mdls", &args) else {
+                continue;
+            };
+            for (path, last_used) in chunk.iter().zip(parse_mdls_dates(&dates)) {
+                let Some(last_used) = last_used else {
+                    continue;
+                };
+                let path = PathBuf::from(path);
+                files.push(RecentFile {
+                    name: file_name(&path),
+                    last_used: last_used.to_rfc3339(),
+                    path: path.display().to_string(),
+                    applications: Vec::new(),
+                });
+            }
+        }
+        files
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // The shell keeps a shortcut per recently opened document; WScript.Shell
+        // resolves each one to the document it points at
+        let Some(recent) = std::env::var_os("APPDATA")
+            .map(|dir| PathBuf::from(dir).join(r"Microsoft\Windows\Recent"))
+        else {
+            return Vec::new();
+        };
+        let script = format!(
+            "[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+             $shell = New-Object -ComObject WScript.Shell; \
+             Get-ChildItem -LiteralPath '{}' -Filter *.lnk | ForEach-Object {{ \
+             \"$($_.FullName)`t$($shell.CreateShortcut($_.FullName).TargetPath)\" }}",
+            recent.display().to_string().replace('\'', "''")
+        );
+        let Ok(output) = platformLib::current()
+            .runner
+            .output("powershell", &["-NoProfile", "-Command", &script])
+        else {
+            return Vec::new();
+        };
+        output
+            .lines()
+            .filter_map(|line| {
+                let (shortcut, target) = line.split_once('\t')?;
+                let target = PathBuf::from(target.trim());
+                if target.as_os_str().is_empty() {
+                    return None;
+                }
+                // The shortcut is rewritten each time its document is opened
+                let modified = std::fs::metadata(shortcut)
+                    .and_then(|m| m.modified())
+                    .ok()?;
+                Some(RecentFile {
+                    name: file_name(&target),
+                    last_used: DateTime::<Local>::from(modified).to_rfc3339(),
+                    path: target.display().to_string(),
+                    applications: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Recently used files that still exist, most recent first.
+pub fn list_recent(application: Option<&str>) -> Vec<RecentFile> {
+    let mut files: Vec<RecentFile> = recent_files()
+        .into_iter()
+        .filter(|file| Path::new(&file.path).exists())
+        .filter(|file| match application {
+            Some(app) => file
+                .applications
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(app)),
+            None => true,
+        })
+        .collect();
+    // Compared as instants; the strings' offsets differ across DST changes
+    files.sort_by_cached_key(|file| Reverse(DateTime::parse_from_rfc3339(&file.last_used).ok()));
+    files
+}
+
+// Lists recent files, optionally only those opened by `application`
+#[tauri::command]
+pub fn list_recent_files(application: Option<String>, limit: Option<usize>) -> Vec<RecentFile> {
+    let mut files = list_recent(application.as_deref());
+    if let Some(limit) = limit {
+        files.truncate(limit);
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XBEL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0" xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks">
+  <bookmark href="file:///home/me/My%20Report.pdf" added="2024-01-01T10:00:00Z" modified="2024-01-02T10:00:00Z" visited="2024-01-01T12:00:00Z">
+    <info><metadata owner="http://freedesktop.org">
+      <bookmark:applications>
+        <bookmark:application name="Evince" exec="&apos;evince %u&apos;" modified="2024-01-05T08:30:00Z" count="2"/>
+        <bookmark:application name="Firefox" exec="&apos;firefox %u&apos;" modified="2024-01-03T08:30:00Z" count="1"/>
+      </bookmark:applications>
+    </metadata></info>
+  </bookmark>
+  <bookmark href="https://example.com/remote.txt" added="2024-01-06T10:00:00Z"/>
+  <bookmark href="file:///home/me/notes.txt" added="2024-01-07T09:00:00+02:00"/>
+  <bookmark href="file:///home/me/undated.txt"/>
+  <bookmark href="file:///tmp/last.txt" modified="2024-01-08T00:00:00Z">
+  </bookmark>
+</xbel>"#;
+
+    fn at(rfc3339: &str) -> String {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Local)
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn reads_bookmarks_with_their_applications() {
+        let files = parse_xbel(XBEL);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/home/me/My Report.pdf",
+                "/home/me/notes.txt",
+                "/tmp/last.txt"
+            ]
+        );
+
+        let report = &files[0];
+        assert_eq!(report.name, "My Report.pdf");
+        assert_eq!(report.applications, ["Evince", "Firefox"]);
+        assert_eq!(report.last_used, at("2024-01-05T08:30:00Z"));
+    }
+
+    #[test]
+    fn self_closing_bookmarks_stand_alone() {
+        let files = parse_xbel(XBEL);
+        let notes = &files[1];
+        assert_eq!(notes.last_used, at("2024-01-07T07:00:00Z"));
+        assert!(notes.applications.is_empty());
+        // The self-closing bookmark before it doesn't swallow the next one
+        assert_eq!(files[2].last_used, at("2024-01-08T00:00:00Z"));
+    }
+
+    #[test]
+    fn reads_spotlight_last_used_dates() {
+        let dates = parse_mdls_dates(
+            &[
+                "2024-01-02 03:04:05 +0000",
+                "(null)",
+                "2024-06-01 12:00:00 +0200",
+            ]
+            .join("\0"),
+        );
+        assert_eq!(dates.len(), 3);
+        assert_eq!(
+            dates[0].map(|d| d.to_rfc3339()),
+            Some(at("2024-01-02T03:04:05Z"))
+        );
+        assert_eq!(dates[1], None);
+        assert_eq!(
+            dates[2].map(|d| d.to_rfc3339()),
+            Some(at("2024-06-01T10:00:00Z"))
+        );
+    }
+}
@@ -1,9 +1,45 @@
+use crate::frecencyLib::{self, UsageKind};
+use crate::fuzzyLib;
+use crate::recentFilesLib::{self, RecentFile};
+use chrono::DateTime;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+#[cfg(not(target_os = "linux"))]
 use std::path::Path;
-use strsim;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
+// Searches run on every keystroke; reread the recent files at most this often
+const RECENT_FILES_TTL: Duration = Duration::from_secs(5);
+
+// When the recent files were read, and what they were
+type RecentCache = Option<(Instant, Vec<RecentFile>)>;
+
+static RECENT_FILES: Lazy<Mutex<RecentCache>> = Lazy::new(|| Mutex::new(None));
+
+fn recent_files() -> Vec<RecentFile> {
+    let mut cache = RECENT_FILES.lock().unwrap();
+    if let Some((loaded, files)) = cache.as_ref() {
+        if loaded.elapsed() < RECENT_FILES_TTL {
+            return files.clone();
+        }
+    }
+    let files = recentFilesLib::list_recent(None);
+    *cache = Some((Instant::now(), files.clone()));
+    files
+}
+
+#[derive(Serialize)]
+pub struct FileMatch {
+    pub path: String,
+    pub score: i64,
+    pub indices: Vec<usize>,
+    pub recent: bool,
+}
+
 #[tauri::command]
-pub fn search_files(query: &str) -> Result<Vec<String>, String> {
+pub fn search_files(query: &str) -> Result<Vec<FileMatch>, String> {
     use std::cmp::Reverse;
     use std::collections::BinaryHeap;
 
@@ -13,20 +49,53 @@ pub fn search_files(query: &str) -> Result<Vec<String>, String> {
     #[cfg(target_os = "windows")]
     let search_paths = vec![Path::new("C:\\"), Path::new("D:\\"), Path::new("E:\\")];
 
+    // Walking `/` on Linux would descend into /proc and /sys
+    #[cfg(target_os = "linux")]
+    let search_paths = vec![std::env::var_os("HOME")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| "/".into())];
+
     let mut heap = BinaryHeap::new();
     let mut seen = std::collections::HashSet::new();
 
+    for recent in recent_files() {
+        if let Some(m) = fuzzyLib::fuzzy_match_path(query, &recent.path) {
+            if seen.insert(std::path::PathBuf::from(&recent.path)) {
+                // Opening a document elsewhere counts like one pick in the launcher
+                let opened = DateTime::parse_from_rfc3339(&recent.last_used)
+                    .map_or(0, |t| frecencyLib::recency_boost(t.timestamp()));
+                let score =
+                    m.score + opened + frecencyLib::rank_boost(UsageKind::File, &recent.path);
+                heap.push(Reverse((
+                    score,
+                    Reverse(recent.path.len()),
+                    recent.path,
+                    m.indices,
+                    true,
+                )));
+                if heap.len() > 8 {
+                    heap.pop();
+                }
+            }
+        }
+    }
+
     for root in search_paths {
         let walker = WalkDir::new(root).into_iter();
         for entry in walker.filter_map(|e| e.ok()).take(100_000) {
             let path = entry.path();
             if path.is_file() {
-                if let Some(name) = Path::new(path).file_name().and_then(|n| n.to_str()) {
-                    let score = strsim::jaro_winkler(query, name);
-                    if score > 0.6 && seen.insert(path.to_path_buf()) {
+                let path_str = path.display().to_string();
+                if let Some(m) = fuzzyLib::fuzzy_match_path(query, &path_str) {
+                    if seen.insert(path.to_path_buf()) {
+                        let score = m.score + frecencyLib::rank_boost(UsageKind::File, &path_str);
+                        // Shorter paths win ties
                         heap.push(Reverse((
-                            (score * 10000.0) as i32,
-                            path.display().to_string(),
+                            score,
+                            Reverse(path_str.len()),
+                            path_str,
+                            m.indices,
+                            false,
                         )));
                         if heap.len() > 8 {
                             heap.pop();
@@ -37,12 +106,16 @@ pub fn search_files(query: &str) -> Result<Vec<String>, String> {
         }
     }
 
-    let mut results: Vec<_> = heap
+    // Sorting the min-heap's `Reverse` entries ascending yields highest score first
+    let results: Vec<_> = heap
         .into_sorted_vec()
         .into_iter()
-        .map(|Reverse((_, path))| path)
+        .map(|Reverse((score, _, path, indices, recent))| FileMatch {
+            path,
+            score,
+            indices,
+            recent,
+        })
         .collect();
-
-    results.reverse(); // highest score first
     Ok(results)
 }
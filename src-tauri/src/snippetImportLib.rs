@@ -0,0 +1,875 @@
+// Importers for snippets from other launchers and text expanders, and a
+// portable JSON export. Foreign placeholders are translated to ours where
+// there is an equivalent; anything else is kept as literal text and reported.
+
+use crate::placeholdersLib;
+use crate::snippetsLib::{self, Library, Snippet};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+const EXPORT_FORMAT: &str = "astrolaunch-snippets";
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    Raycast,
+    Alfred,
+    Espanso,
+    // Our own export format
+    Astrolaunch,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct UnmappedFeature {
+    snippet: String,
+    feature: String,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct ImportReport {
+    // Ids of the snippets written
+    imported: Vec<String>,
+    skipped: Vec<String>,
+    unmapped: Vec<UnmappedFeature>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PortableExport {
+    format: String,
+    version: u32,
+    snippets: Vec<Snippet>,
+}
+
+// A snippet read from another tool, already translated to our syntax
+struct Imported {
+    name: String,
+    keyword: Option<String>,
+    content: String,
+    unmapped: Vec<String>,
+}
+
+fn escape_literal(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+// `{kind:format}`; braces and quotes would end the placeholder early, so they
+// are split out of `format` as literal text between date placeholders
+fn date_with_format(kind: &str, format: &str) -> String {
+    let mut out = String::new();
+    let mut rest = format;
+    while let Some(pos) = rest.find(['{', '}', '"']) {
+        if pos > 0 {
+            out.push_str(&format!("{{{}:{}}}", kind, &rest[..pos]));
+        }
+        out.push_str(&escape_literal(&rest[pos..pos + 1]));
+        rest = &rest[pos + 1..];
+    }
+    if !rest.is_empty() || out.is_empty() {
+        out.push_str(&format!("{{{}:{}}}", kind, rest));
+    }
+    out
+}
+
+/// Converts a Unicode (LDML) date pattern such as `yyyy-MM-dd HH:mm` to strftime.
+///
+/// Returns `None` for pattern letters without a strftime equivalent.
+pub fn ldml_to_strftime(pattern: &str) -> Option<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' {
+            // Quoted literal; '' is an escaped quote, inside or outside one
+            i += 1;
+            if chars.get(i) == Some(&'\'') {
+                out.push('\'');
+                i += 1;
+                continue;
+            }
+            while i < chars.len() {
+                match chars[i] {
+                    '\'' if chars.get(i + 1) == Some(&'\'') => {
+                        out.push('\'');
+                        i += 1;
+                    }
+                    '\'' => break,
+                    '%' => out.push_str("%%"),
+                    c => out.push(c),
+                }
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        if !c.is_ascii_alphabetic() {
+            if c == '%' {
+                out.push('%');
+            }
+            out.push(c);
+            i += 1;
+            continue;
+        }
+
+        let run = chars[i..].iter().take_while(|&&x| x == c).count();
+        let spec = match (c, run) {
+            ('y', 2) => "%y",
+            ('y', _) => "%Y",
+            ('M', 1) => "%-m",
+            ('M', 2) => "%m",
+            ('M', 3) => "%b",
+            ('M', _) => "%B",
+            ('d', 1) => "%-d",
+            ('d', _) => "%d",
+            ('E', 4) => "%A",
+            ('E', _) => "%a",
+            ('H', 1) => "%-H",
+            ('H', _) => "%H",
+            ('h', 1) => "%-I",
+            ('h', _) => "%I",
+            ('m', 1) => "%-M",
+            ('m', _) => "%M",
+            ('s', 1) => "%-S",
+            ('s', _) => "%S",
+            ('a', _) => "%p",
+            ('Z', _) | ('x', _) => "%z",
+            _ => return None,
+        };
+        out.push_str(spec);
+        i += run;
+    }
+    Some(out)
+}
+
+/// Rewrites `{...}` tokens with `map`, escaping everything else as literal text.
+///
+/// Tokens `map` can't translate are kept verbatim and listed in `unmapped`.
+fn translate_braces<F>(text: &str, unmapped: &mut Vec<String>, map: F) -> String
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&escape_literal(&rest[..open]));
+        let tail = &rest[open..];
+        match tail[1..].find(['{', '}']) {
+            Some(close) if tail.as_bytes()[1 + close] == b'}' => {
+                let inner = &tail[1..1 + close];
+                if inner.trim().is_empty() {
+                    out.push_str(&escape_literal(&tail[..close + 2]));
+                    rest = &tail[close + 2..];
+                    continue;
+                }
+                match map(inner.trim()) {
+                    Ok(placeholder) => out.push_str(&placeholder),
+                    Err(feature) => {
+                        unmapped.push(feature);
+                        out.push_str(&escape_literal(&tail[..close + 2]));
+                    }
+                }
+                rest = &tail[close + 2..];
+            }
+            _ => {
+                out.push_str("{{");
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(&escape_literal(rest));
+    out
+}
+
+fn date_placeholder(kind: &str, ldml: Option<&str>, token: &str) -> Result<String, String> {
+    match ldml {
+        None => Ok(format!("{{{}}}", kind)),
+        Some(pattern) => ldml_to_strftime(pattern)
+            .map(|format| date_with_format(kind, &format))
+            .ok_or_else(|| format!("{{{}}} (date format)", token)),
+    }
+}
+
+fn map_raycast_token(token: &str) -> Result<String, String> {
+    if token.contains('|') {
+        return Err(format!("{{{}}} (modifiers)", token));
+    }
+    let (name, rest) = token.split_once(' ').unwrap_or((token, ""));
+    let attrs = placeholdersLib::parse_attributes(rest);
+    if attrs.contains_key("offset") {
+        return Err(format!("{{{}}} (offset)", token));
+    }
+
+    match name {
+        "clipboard" | "cursor" | "uuid" if attrs.is_empty() => Ok(format!("{{{}}}", name)),
+        "date" | "time" | "datetime" => {
+            date_placeholder(name, attrs.get("format").map(String::as_str), token)
+        }
+        "day" => Ok("{date:%A}".into()),
+        "argument" if !attrs.contains_key("options") => {
+            let name = attrs.get("name").ok_or_else(|| format!("{{{}}}", token))?;
+            Ok(match attrs.get("default") {
+                Some(default) => format!("{{argument name=\"{}\" default=\"{}\"}}", name, default),
+                None => format!("{{argument name=\"{}\"}}", name),
+            })
+        }
+        "snippet" => attrs
+            .get("name")
+            .map(|name| format!("{{snippet name=\"{}\"}}", safe_name(name)))
+            .ok_or_else(|| format!("{{{}}}", token)),
+        _ => Err(format!("{{{}}}", token)),
+    }
+}
+
+fn map_alfred_token(token: &str) -> Result<String, String> {
+    let (name, arg) = match token.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (token, None),
+    };
+    if name.contains(' ') {
+        // Date arithmetic such as {date +1d}
+        return Err(format!("{{{}}} (offset)", token));
+    }
+
+    match (name, arg) {
+        ("clipboard", None) | ("cursor", None) => Ok(format!("{{{}}}", name)),
+        ("date", None) | ("time", None) | ("datetime", None) => Ok(format!("{{{}}}", name)),
+        ("date", Some("short")) => Ok("{date:%x}".into()),
+        ("date", Some("medium")) => Ok("{date:%b %-d, %Y}".into()),
+        ("date", Some("long")) => Ok("{date:%B %-d, %Y}".into()),
+        ("date", Some("full")) => Ok("{date:%A, %B %-d, %Y}".into()),
+        ("time", Some("short")) => Ok("{time:%H:%M}".into()),
+        ("time", Some("medium")) | ("time", Some("long")) => Ok("{time:%H:%M:%S}".into()),
+        ("isodate", None) => Ok("{datetime:%Y-%m-%dT%H:%M:%S%z}".into()),
+        ("isodate", Some(pattern)) => date_placeholder("datetime", Some(pattern), token),
+        _ => Err(format!("{{{}}}", token)),
+    }
+}
+
+// Snippet names become file names; keep them within one path segment
+fn safe_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let name = name.trim_start_matches('.').to_string();
+    if name.is_empty() {
+        "snippet".into()
+    } else {
+        name
+    }
+}
+
+fn read_raycast(path: &Path) -> Result<Vec<Imported>, String> {
+    #[derive(Deserialize)]
+    struct RaycastSnippet {
+        name: String,
+        text: String,
+        keyword: Option<String>,
+    }
+
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let snippets: Vec<RaycastSnippet> =
+        serde_json::from_str(&json).map_err(|e| format!("Not a Raycast snippet export: {}", e))?;
+
+    Ok(snippets
+        .into_iter()
+        .map(|s| {
+            let mut unmapped = Vec::new();
+            let content = translate_braces(&s.text, &mut unmapped, map_raycast_token);
+            Imported {
+                name: s.name,
+                keyword: s.keyword,
+                content,
+                unmapped,
+            }
+        })
+        .collect())
+}
+
+fn read_alfred(path: &Path) -> Result<Vec<Imported>, String> {
+    #[derive(Deserialize)]
+    struct AlfredFile {
+        alfredsnippet: AlfredSnippet,
+    }
+    #[derive(Deserialize)]
+    struct AlfredSnippet {
+        snippet: String,
+        name: String,
+        keyword: Option<String>,
+    }
+
+    // A collection is a zip of JSON files; an extracted folder or single file works too
+    let mut documents: Vec<String> = Vec::new();
+    if path.is_dir() {
+        for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+            if entry.path().extension().is_some_and(|ext| ext == "json") {
+                documents.push(fs::read_to_string(entry.path()).map_err(|e| e.to_string())?);
+            }
+        }
+    } else if path.extension().is_some_and(|ext| ext == "json") {
+        documents.push(fs::read_to_string(path).map_err(|e| e.to_string())?);
+    } else {
+        use std::io::Read;
+
+        let file = fs::File::open(path).map_err(|e| e.to_string())?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| format!("Not an Alfred snippet collection: {}", e))?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            if entry.name().ends_with(".json") {
+                let mut json = String::new();
+                entry.read_to_string(&mut json).map_err(|e| e.to_string())?;
+                documents.push(json);
+            }
+        }
+    }
+
+    documents
+        .iter()
+        .map(|json| {
+            let file: AlfredFile =
+                serde_json::from_str(json).map_err(|e| format!("Not an Alfred snippet: {}", e))?;
+            let s = file.alfredsnippet;
+            let mut unmapped = Vec::new();
+            let content = translate_braces(&s.snippet, &mut unmapped, map_alfred_token);
+            Ok(Imported {
+                name: s.name,
+                keyword: s.keyword.filter(|k| !k.is_empty()),
+                content,
+                unmapped,
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct EspansoVar {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    params: serde_yaml_ng::Mapping,
+}
+
+#[derive(Deserialize)]
+struct EspansoMatch {
+    trigger: Option<String>,
+    #[serde(default)]
+    triggers: Vec<String>,
+    replace: Option<String>,
+    markdown: Option<String>,
+    html: Option<String>,
+    form: Option<String>,
+    image_path: Option<String>,
+    label: Option<String>,
+    #[serde(default)]
+    vars: Vec<EspansoVar>,
+    #[serde(default)]
+    regex: Option<String>,
+}
+
+fn translate_espanso(m: &EspansoMatch) -> Option<Imported> {
+    let mut unmapped = Vec::new();
+    let keyword = m.trigger.clone().or_else(|| m.triggers.first().cloned());
+    if m.triggers.len() > 1 {
+        unmapped.push(format!("additional triggers {:?}", &m.triggers[1..]));
+    }
+    if m.regex.is_some() {
+        unmapped.push("regex trigger".into());
+    }
+
+    let (body, from_form) = match (&m.replace, &m.markdown, &m.html, &m.form) {
+        (Some(text), _, _, _) => (text.clone(), false),
+        (_, Some(text), _, _) => {
+            unmapped.push("markdown formatting".into());
+            (text.clone(), false)
+        }
+        (_, _, Some(text), _) => {
+            unmapped.push("html formatting".into());
+            (text.clone(), false)
+        }
+        (_, _, _, Some(text)) => (text.clone(), true),
+        _ => return None,
+    };
+
+    let param = |var: &EspansoVar, key: &str| -> Option<String> {
+        var.params
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+    let var_placeholder = |name: &str, unmapped: &mut Vec<String>| -> String {
+        // Form fields are referenced as {{form_var.field}}
+        let var_name = name.split('.').next().unwrap_or(name);
+        match m.vars.iter().find(|v| v.name == var_name) {
+            Some(var) if var.kind == "date" => match param(var, "format") {
+                Some(format) => date_with_format("date", &format),
+                None => "{date}".into(),
+            },
+            Some(var) if var.kind == "clipboard" => "{clipboard}".into(),
+            Some(var) if var.kind == "echo" => {
+                escape_literal(&param(var, "echo").unwrap_or_default())
+            }
+            Some(var) if var.kind == "form" && name.contains('.') => {
+                format!("{{argument name=\"{}\"}}", &name[var_name.len() + 1..])
+            }
+            Some(var) => {
+                unmapped.push(format!("{} variable '{}'", var.kind, var.name));
+                escape_literal(&format!("{{{{{}}}}}", name))
+            }
+            None => {
+                unmapped.push(format!("global variable '{}'", name));
+                escape_literal(&format!("{{{{{}}}}}", name))
+            }
+        }
+    };
+
+    let mut content = String::new();
+    let mut rest = body.as_str();
+    loop {
+        let next = [
+            rest.find("{{").map(|i| (i, "{{")),
+            rest.find("$|$").map(|i| (i, "$|$")),
+            rest.find("[[").filter(|_| from_form).map(|i| (i, "[[")),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by_key(|(i, _)| *i);
+
+        let Some((start, marker)) = next else {
+            content.push_str(&escape_literal(rest));
+            break;
+        };
+        content.push_str(&escape_literal(&rest[..start]));
+        let after = &rest[start + marker.len()..];
+
+        match marker {
+            "$|$" => {
+                content.push_str("{cursor}");
+                rest = after;
+            }
+            "{{" => match after.find("}}") {
+                Some(end) => {
+                    content.push_str(&var_placeholder(after[..end].trim(), &mut unmapped));
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    content.push_str("{{{{");
+                    rest = after;
+                }
+            },
+            _ => match after.find("]]") {
+                Some(end) => {
+                    content.push_str(&format!("{{argument name=\"{}\"}}", after[..end].trim()));
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    content.push_str("[[");
+                    rest = after;
+                }
+            },
+        }
+    }
+
+    // ":sig" makes a better file name as "sig"
+    let name = m
+        .label
+        .clone()
+        .or_else(|| {
+            keyword.as_deref().map(|k| {
+                k.trim_start_matches(|c: char| !c.is_alphanumeric())
+                    .to_string()
+            })
+        })
+        .unwrap_or_else(|| "snippet".into());
+    Some(Imported {
+        name,
+        keyword,
+        content,
+        unmapped,
+    })
+}
+
+fn read_espanso(path: &Path) -> Result<(Vec<Imported>, Vec<String>), String> {
+    #[derive(Deserialize)]
+    struct MatchFile {
+        #[serde(default)]
+        matches: Vec<EspansoMatch>,
+    }
+
+    let files: Vec<_> = if path.is_dir() {
+        WalkDir::new(path)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|ext| ext == "yml" || ext == "yaml")
+            })
+            .collect()
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    for file in files {
+        let yaml = fs::read_to_string(&file).map_err(|e| e.to_string())?;
+        let parsed: MatchFile = serde_yaml_ng::from_str(&yaml)
+            .map_err(|e| format!("{}: not an espanso match file: {}", file.display(), e))?;
+        for m in parsed.matches {
+            if m.image_path.is_some() {
+                skipped.push(format!(
+                    "{} (image matches are not supported)",
+                    m.trigger.as_deref().unwrap_or("?")
+                ));
+                continue;
+            }
+            match translate_espanso(&m) {
+                Some(snippet) => imported.push(snippet),
+                None => skipped.push(format!(
+                    "{} (no replacement text)",
+                    m.trigger.as_deref().unwrap_or("?")
+                )),
+            }
+        }
+    }
+    Ok((imported, skipped))
+}
+
+fn read_portable(path: &Path) -> Result<Vec<Snippet>, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let export: PortableExport =
+        serde_json::from_str(&json).map_err(|e| format!("Not an AstroLaunch export: {}", e))?;
+    if export.format != EXPORT_FORMAT || export.version > EXPORT_VERSION {
+        return Err(format!(
+            "Unsupported export format {} v{}",
+            export.format, export.version
+        ));
+    }
+    Ok(export.snippets)
+}
+
+// Writes `snippets` under free ids, suffixing "-2", "-3", ... on collisions,
+// and points includes of a renamed snippet at its new id
+fn write_snippets(
+    library: &Library,
+    mut snippets: Vec<Snippet>,
+    report: &mut ImportReport,
+) -> Result<(), String> {
+    let mut taken = HashSet::new();
+    // An include of a name imported twice refers to the first
+    let mut renamed = HashMap::new();
+    for snippet in &mut snippets {
+        let base = snippet.id.clone();
+        let mut n = 1;
+        while library.exists(&snippet.id) || taken.contains(&snippet.id) {
+            n += 1;
+            snippet.id = format!("{}-{}", base, n);
+        }
+        taken.insert(snippet.id.clone());
+        renamed.entry(base).or_insert_with(|| snippet.id.clone());
+    }
+    renamed.retain(|base, id| base != id);
+
+    for mut snippet in snippets {
+        snippet.content = placeholdersLib::rename_includes(&snippet.content, &renamed);
+        let (category, name) = match snippet.id.rsplit_once('/') {
+            Some((category, name)) => (Some(category), name),
+            None => (None, snippet.id.as_str()),
+        };
+        let contents = snippetsLib::render_snippet_file(&snippet)?;
+        let id = library.create(name, &contents, category)?;
+        report.imported.push(id);
+    }
+    Ok(())
+}
+
+// Places a foreign snippet in `category`, noting what didn't translate
+fn translated_snippet(snippet: Imported, category: &str, report: &mut ImportReport) -> Snippet {
+    let name = safe_name(&snippet.name);
+    let id = if category.is_empty() {
+        name.clone()
+    } else {
+        format!("{}/{}", category, name)
+    };
+    report
+        .unmapped
+        .extend(snippet.unmapped.into_iter().map(|feature| UnmappedFeature {
+            snippet: name.clone(),
+            feature,
+        }));
+
+    // Includes refer to snippets imported alongside, in the same category
+    let content = if category.is_empty() {
+        snippet.content
+    } else {
+        snippet.content.replace(
+            "{snippet name=\"",
+            &format!("{{snippet name=\"{}/", category),
+        )
+    };
+
+    Snippet {
+        id,
+        title: snippet.name.trim().to_string(),
+        keyword: snippet.keyword,
+        tags: Vec::new(),
+        description: None,
+        language: None,
+        category: None,
+        content,
+    }
+}
+
+fn import_into(
+    library: &Library,
+    source: ImportSource,
+    path: &Path,
+    category: Option<String>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+
+    let category = category.unwrap_or_else(|| {
+        match source {
+            ImportSource::Raycast => "raycast",
+            ImportSource::Alfred => "alfred",
+            ImportSource::Espanso => "espanso",
+            ImportSource::Astrolaunch => "",
+        }
+        .to_string()
+    });
+    let category = category.trim_matches('/');
+
+    let snippets: Vec<Snippet> = match source {
+        ImportSource::Astrolaunch => read_portable(path)?
+            .into_iter()
+            .map(|mut snippet| {
+                if !category.is_empty() {
+                    snippet.id = format!("{}/{}", category, snippet.id);
+                }
+                snippet
+            })
+            .collect(),
+        _ => {
+            let imported = match source {
+                ImportSource::Raycast => read_raycast(path)?,
+                ImportSource::Alfred => read_alfred(path)?,
+                ImportSource::Espanso => {
+                    let (imported, skipped) = read_espanso(path)?;
+                    report.skipped = skipped;
+                    imported
+                }
+                ImportSource::Astrolaunch => unreachable!(),
+            };
+            imported
+                .into_iter()
+                .map(|snippet| translated_snippet(snippet, category, &mut report))
+                .collect()
+        }
+    };
+
+    // Check everything first so a bad entry doesn't leave half an import behind
+    for snippet in &snippets {
+        snippetsLib::validate_id(&snippet.id)?;
+        snippetsLib::render_snippet_file(snippet)?;
+    }
+    write_snippets(library, snippets, &mut report)?;
+
+    Ok(report)
+}
+
+// Imports into `category` (defaults to the source's name) and reports what didn't translate
+#[tauri::command]
+pub fn import_snippets(
+    source: ImportSource,
+    path: &str,
+    category: Option<String>,
+) -> Result<ImportReport, String> {
+    import_into(&Library::open()?, source, Path::new(path), category)
+}
+
+// Writes every snippet, metadata included, to a portable JSON file
+#[tauri::command]
+pub fn export_snippets(path: &str) -> Result<usize, String> {
+    let snippets = snippetsLib::load_snippets()?;
+    let count = snippets.len();
+    let export = PortableExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        snippets,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_ldml_date_patterns() {
+        assert_eq!(
+            ldml_to_strftime("yyyy-MM-dd HH:mm").as_deref(),
+            Some("%Y-%m-%d %H:%M")
+        );
+        assert_eq!(
+            ldml_to_strftime("EEEE, d MMMM").as_deref(),
+            Some("%A, %-d %B")
+        );
+        assert_eq!(ldml_to_strftime("QQQ"), None);
+    }
+
+    #[test]
+    fn escapes_percent_signs_in_literals() {
+        assert_eq!(ldml_to_strftime("d'%'").as_deref(), Some("%-d%%"));
+        assert_eq!(ldml_to_strftime("d%").as_deref(), Some("%-d%%"));
+        assert_eq!(
+            ldml_to_strftime("h 'o''clock'").as_deref(),
+            Some("%-I o'clock")
+        );
+    }
+
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("snippet-import-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn translate(text: &str, map: fn(&str) -> Result<String, String>) -> (String, Vec<String>) {
+        let mut unmapped = Vec::new();
+        let content = translate_braces(text, &mut unmapped, map);
+        (content, unmapped)
+    }
+
+    fn expand_now(template: &str) -> String {
+        placeholdersLib::expand(template, &HashMap::new(), &|name| Err(name.to_string()))
+            .unwrap()
+            .text
+    }
+
+    #[test]
+    fn translates_raycast_placeholders() {
+        let (content, unmapped) = translate(
+            "Hi {argument name=\"Who\" default=\"you\"}, {date format=\"yyyy\"} {clipboard | uppercase} {snippet name=\"sig\"} {}",
+            map_raycast_token,
+        );
+        assert_eq!(
+            content,
+            "Hi {argument name=\"Who\" default=\"you\"}, {date:%Y} {{clipboard | uppercase}} {snippet name=\"sig\"} {{}}"
+        );
+        assert_eq!(unmapped, ["{clipboard | uppercase} (modifiers)"]);
+
+        let (_, unmapped) = translate("{date offset=\"+1d\"} {selection}", map_raycast_token);
+        assert_eq!(unmapped, ["{date offset=\"+1d\"} (offset)", "{selection}"]);
+    }
+
+    #[test]
+    fn translates_alfred_placeholders() {
+        let (content, unmapped) = translate(
+            "{date:long} at {time:short}{cursor} {isodate:yyyy} {date +1d} {random:1,2}",
+            map_alfred_token,
+        );
+        assert_eq!(
+            content,
+            "{date:%B %-d, %Y} at {time:%H:%M}{cursor} {datetime:%Y} {{date +1d}} {{random:1,2}}"
+        );
+        assert_eq!(unmapped, ["{date +1d} (offset)", "{random:1,2}"]);
+    }
+
+    #[test]
+    fn translates_espanso_matches() {
+        let m: EspansoMatch = serde_yaml_ng::from_str(
+            r#"
+trigger: ":today"
+replace: "{{now}} $|${{clip}} {{name.first}} {{shell}} {{global}} {x}"
+vars:
+  - name: now
+    type: date
+    params:
+      format: "{%Y} \"q\""
+  - name: clip
+    type: clipboard
+  - name: name
+    type: form
+  - name: shell
+    type: shell
+"#,
+        )
+        .unwrap();
+        let imported = translate_espanso(&m).unwrap();
+        assert_eq!(imported.name, "today");
+        assert_eq!(imported.keyword.as_deref(), Some(":today"));
+        assert_eq!(
+            imported.content,
+            "{{{date:%Y}}}{date: }\"{date:q}\" {cursor}{clipboard} {argument name=\"first\"} \
+             {{{{shell}}}} {{{{global}}}} {{x}}"
+        );
+        assert_eq!(
+            imported.unmapped,
+            ["shell variable 'shell'", "global variable 'global'"]
+        );
+
+        let year = chrono::Local::now().format("%Y").to_string();
+        let date = imported.content.split(" {cursor}").next().unwrap();
+        assert_eq!(expand_now(date), format!("{{{}}} \"q\"", year));
+    }
+
+    #[test]
+    fn keeps_braces_and_quotes_out_of_date_formats() {
+        assert_eq!(date_with_format("time", "%H:%M"), "{time:%H:%M}");
+        assert_eq!(date_with_format("date", "{}"), "{{}}");
+        assert_eq!(
+            date_placeholder("date", Some("'{'yyyy'}'"), "date").unwrap(),
+            "{{{date:%Y}}}"
+        );
+    }
+
+    #[test]
+    fn renamed_imports_keep_their_includes() {
+        let temp = TempDir::new();
+        let library = Library::new(temp.0.clone());
+        library.create("sig", "Existing", Some("raycast")).unwrap();
+        let export = temp.0.join("raycast.json");
+        fs::write(
+            &export,
+            r#"[
+                {"name": "sig", "text": "Regards"},
+                {"name": "reply", "text": "Thanks!\n{snippet name=\"sig\"}", "keyword": ";re"}
+            ]"#,
+        )
+        .unwrap();
+
+        let report = import_into(&library, ImportSource::Raycast, &export, None).unwrap();
+        assert_eq!(report.imported, ["raycast/sig-2", "raycast/reply"]);
+        let reply = library.get(";re").unwrap();
+        assert_eq!(reply.content, "Thanks!\n{snippet name=\"raycast/sig-2\"}");
+        assert_eq!(library.read("raycast/sig").unwrap(), "Existing");
+        let (_, expansion) = library.expand("raycast/reply", &HashMap::new()).unwrap();
+        assert_eq!(expansion.text, "Thanks!\nRegards");
+    }
+}
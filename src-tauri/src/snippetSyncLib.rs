@@ -0,0 +1,520 @@
+// Optional sync of the snippet directory through a git remote, which may be
+// a plain path to a bare repository on a shared drive.
+//
+// Local edits are committed, merged with the remote and pushed. A merge
+// conflict is aborted and reported, with the remote commit it happened
+// against, instead of overwriting either side.
+
+use crate::commandLib::CommandRunner;
+use crate::platformLib;
+use crate::snippetsLib;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Emitter;
+
+const DEFAULT_BRANCH: &str = "main";
+// Wait for edits to settle before committing them
+const SYNC_DEBOUNCE: Duration = Duration::from_secs(3);
+// Used only when git has no identity configured
+const FALLBACK_NAME: &str = "AstroLaunch";
+const FALLBACK_EMAIL: &str = "astrolaunch@localhost";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SyncSettings {
+    // Remote URL or path; `None` turns sync off
+    remote: Option<String>,
+    branch: String,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        SyncSettings {
+            remote: None,
+            branch: DEFAULT_BRANCH.into(),
+            path: None,
+        }
+    }
+}
+
+impl SyncSettings {
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStatus {
+    Disabled,
+    UpToDate,
+    Pulled,
+    Pushed,
+    // Both sides changed; nothing was merged, `conflicts` lists the files and
+    // `remote_head` is the commit to settle them against
+    Conflict,
+}
+
+// How to settle a conflict reported by a previous sync
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    KeepLocal,
+    KeepRemote,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SyncResult {
+    pub status: SyncStatus,
+    pub conflicts: Vec<String>,
+    pub remote_head: Option<String>,
+}
+
+impl SyncResult {
+    fn status(status: SyncStatus) -> Self {
+        SyncResult {
+            status,
+            conflicts: Vec::new(),
+            remote_head: None,
+        }
+    }
+}
+
+static SETTINGS: Lazy<Mutex<SyncSettings>> = Lazy::new(|| Mutex::new(SyncSettings::default()));
+// Serializes syncs from the watcher and from commands
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+// A snippet checkout and the runner its git commands go through
+struct Repo<'a> {
+    runner: &'a dyn CommandRunner,
+    dir: &'a Path,
+}
+
+impl Repo<'_> {
+    fn git(&self, args: &[&str]) -> Result<String, String> {
+        let dir = self
+            .dir
+            .to_str()
+            .ok_or_else(|| format!("{} is not a valid UTF-8 path", self.dir.display()))?;
+        let mut full = vec!["-C", dir];
+        full.extend_from_slice(args);
+        let output = self.runner.run("git", &full)?;
+        if output.success {
+            Ok(output.stdout.trim().to_string())
+        } else {
+            Err(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                output.stderr.trim()
+            ))
+        }
+    }
+
+    // `git` with a fallback author for commands that create commits
+    fn git_commit(&self, args: &[&str]) -> Result<String, String> {
+        if self.git(&["config", "user.email"]).is_ok() {
+            return self.git(args);
+        }
+        let name = format!("user.name={}", FALLBACK_NAME);
+        let email = format!("user.email={}", FALLBACK_EMAIL);
+        let mut full = vec!["-c", &name, "-c", &email];
+        full.extend_from_slice(args);
+        self.git(&full)
+    }
+
+    fn ensure(&self, remote: &str, branch: &str) -> Result<(), String> {
+        fs::create_dir_all(self.dir).map_err(|e| e.to_string())?;
+        if !self.dir.join(".git").exists() {
+            self.git(&["init", "--quiet"])?;
+            self.git(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", branch)])?;
+        }
+        match self.git(&["remote", "get-url", "origin"]) {
+            Ok(url) if url == remote => Ok(()),
+            Ok(_) => self
+                .git(&["remote", "set-url", "origin", remote])
+                .map(|_| ()),
+            Err(_) => self.git(&["remote", "add", "origin", remote]).map(|_| ()),
+        }
+    }
+
+    fn commit_local_changes(&self) -> Result<(), String> {
+        self.git(&["add", "--all"])?;
+        if self.git(&["diff", "--cached", "--quiet"]).is_err() {
+            self.git_commit(&["commit", "--quiet", "-m", "Update snippets"])?;
+        }
+        Ok(())
+    }
+
+    fn rev(&self, name: &str) -> Option<String> {
+        self.git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", name),
+        ])
+        .ok()
+    }
+
+    fn merge(
+        &self,
+        commit: &str,
+        strategy: Option<ConflictStrategy>,
+    ) -> Result<Vec<String>, String> {
+        let mut args = vec!["merge", "--no-edit", "--allow-unrelated-histories"];
+        match strategy {
+            Some(ConflictStrategy::KeepLocal) => args.extend(["-X", "ours"]),
+            Some(ConflictStrategy::KeepRemote) => args.extend(["-X", "theirs"]),
+            None => {}
+        }
+        args.push(commit);
+        if self.git_commit(&args).is_ok() {
+            return Ok(Vec::new());
+        }
+
+        let conflicts: Vec<String> = self
+            .git(&["diff", "--name-only", "--diff-filter=U"])?
+            .lines()
+            .map(String::from)
+            .collect();
+        // Put the working tree back to the local version
+        let _ = self.git(&["merge", "--abort"]);
+        if conflicts.is_empty() {
+            return Err("Merging the remote snippets failed".into());
+        }
+        Ok(conflicts)
+    }
+
+    /// Commits local edits, merges the remote branch and pushes the result.
+    /// With a strategy, `against` picks the remote commit an earlier conflict
+    /// was reported for.
+    fn sync(
+        &self,
+        remote: &str,
+        branch: &str,
+        strategy: Option<ConflictStrategy>,
+        against: Option<&str>,
+    ) -> Result<SyncResult, String> {
+        self.ensure(remote, branch)?;
+        self.commit_local_changes()?;
+        self.git(&["fetch", "--quiet", "origin"])?;
+
+        let remote_ref = format!("origin/{}", branch);
+        let before = self.rev("HEAD");
+        let remote_head = match against.filter(|_| strategy.is_some()) {
+            Some(commit) => Some(
+                self.rev(commit)
+                    .ok_or_else(|| format!("Unknown remote commit {}", commit))?,
+            ),
+            None => self.rev(&remote_ref),
+        };
+
+        let mut status = SyncStatus::UpToDate;
+        if let Some(remote_head) = &remote_head {
+            if before.is_none() {
+                // Nothing local yet: start from the remote branch
+                self.git(&["reset", "--quiet", "--hard", remote_head])?;
+                status = SyncStatus::Pulled;
+            } else if self
+                .git(&["merge-base", "--is-ancestor", remote_head, "HEAD"])
+                .is_err()
+            {
+                let conflicts = self.merge(remote_head, strategy)?;
+                if !conflicts.is_empty() {
+                    return Ok(SyncResult {
+                        status: SyncStatus::Conflict,
+                        conflicts,
+                        remote_head: Some(remote_head.clone()),
+                    });
+                }
+                status = SyncStatus::Pulled;
+            }
+        }
+
+        let head = self.rev("HEAD");
+        if head.is_some() && head != self.rev(&remote_ref) {
+            self.git(&["push", "--quiet", "origin", &format!("HEAD:{}", branch)])?;
+            status = SyncStatus::Pushed;
+        }
+        Ok(SyncResult::status(status))
+    }
+}
+
+/// Syncs the snippet directory with the configured remote, if any.
+pub fn sync(
+    strategy: Option<ConflictStrategy>,
+    against: Option<&str>,
+) -> Result<SyncResult, String> {
+    let _guard = SYNC_LOCK.lock().unwrap();
+    let settings = SETTINGS.lock().unwrap().clone();
+    let Some(remote) = settings.remote else {
+        return Ok(SyncResult::status(SyncStatus::Disabled));
+    };
+    let dir = snippetsLib::snippet_dir()?;
+    let runner = platformLib::current().runner.clone();
+    Repo {
+        runner: &*runner,
+        dir: &dir,
+    }
+    .sync(&remote, &settings.branch, strategy, against)
+}
+
+fn sync_and_notify(app: &tauri::AppHandle) {
+    match sync(None, None) {
+        // Pulled files are picked up by the `snippets-changed` watcher
+        Ok(result) => {
+            let _ = app.emit("snippet-sync", result);
+        }
+        Err(e) => {
+            let _ = app.emit("snippet-sync-error", e);
+        }
+    }
+}
+
+// Load settings, pull on startup and sync again whenever snippets change
+pub fn init(app: tauri::AppHandle, data_dir: PathBuf) {
+    let path = data_dir.join("snippet_sync.json");
+    let mut settings: SyncSettings = fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    settings.path = Some(path);
+    *SETTINGS.lock().unwrap() = settings;
+
+    std::thread::spawn(move || {
+        sync_and_notify(&app);
+
+        // A pull rewrites files while syncing, which the watcher doesn't count as an edit
+        let watched = snippetsLib::Library::open()
+            .and_then(|library| library.watch(SYNC_DEBOUNCE, move || sync_and_notify(&app)));
+        if let Err(e) = watched {
+            eprintln!("Failed to watch snippets for sync: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_snippet_sync_settings() -> SyncSettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+// Sets the remote to sync with; `None` disables sync but keeps the history
+#[tauri::command]
+pub fn configure_snippet_sync(
+    remote: Option<String>,
+    branch: Option<String>,
+) -> Result<SyncResult, String> {
+    {
+        let mut settings = SETTINGS.lock().unwrap();
+        settings.remote = remote.filter(|r| !r.trim().is_empty());
+        settings.branch = branch
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_BRANCH.into());
+        settings.save()?;
+    }
+    sync(None, None)
+}
+
+// Syncs now; pass a strategy and the reported `remote_head` to settle
+// conflicts from an earlier sync
+#[tauri::command]
+pub fn sync_snippets(
+    strategy: Option<ConflictStrategy>,
+    remote_head: Option<String>,
+) -> Result<SyncResult, String> {
+    sync(strategy, remote_head.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commandLib::SystemRunner;
+
+    // A bare "team" repository and checkouts of it, removed when dropped
+    struct Remote(PathBuf);
+
+    impl Remote {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("snippet-sync-{}", uuid::Uuid::new_v4()));
+            let bare = root.join("team.git");
+            fs::create_dir_all(&bare).unwrap();
+            Repo {
+                runner: &SystemRunner,
+                dir: &bare,
+            }
+            .git(&["init", "--quiet", "--bare"])
+            .unwrap();
+            Remote(root)
+        }
+
+        fn url(&self) -> String {
+            self.0.join("team.git").display().to_string()
+        }
+
+        fn checkout(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+
+        fn try_sync(
+            &self,
+            dir: &Path,
+            strategy: Option<ConflictStrategy>,
+            against: Option<&str>,
+        ) -> Result<SyncResult, String> {
+            Repo {
+                runner: &SystemRunner,
+                dir,
+            }
+            .sync(&self.url(), DEFAULT_BRANCH, strategy, against)
+        }
+
+        fn sync(&self, dir: &Path) -> SyncResult {
+            self.try_sync(dir, None, None).unwrap()
+        }
+    }
+
+    impl Drop for Remote {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn git_installed() -> bool {
+        let installed = SystemRunner.output("git", &["--version"]).is_ok();
+        if !installed {
+            eprintln!("git is not installed; skipping");
+        }
+        installed
+    }
+
+    fn write(dir: &Path, file: &str, contents: &str) {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn read(dir: &Path, file: &str) -> String {
+        fs::read_to_string(dir.join(file)).unwrap()
+    }
+
+    #[test]
+    fn round_trips_through_a_bare_repository() {
+        if !git_installed() {
+            return;
+        }
+        let remote = Remote::new();
+        let alice = remote.checkout("alice");
+        let bob = remote.checkout("bob");
+
+        write(&alice, "hello.md", "Hello!");
+        assert_eq!(remote.sync(&alice).status, SyncStatus::Pushed);
+
+        assert_eq!(remote.sync(&bob).status, SyncStatus::Pulled);
+        assert_eq!(read(&bob, "hello.md"), "Hello!");
+
+        write(&bob, "work/sig.md", "Bob");
+        assert_eq!(remote.sync(&bob).status, SyncStatus::Pushed);
+
+        // Alice's own edit merges cleanly with Bob's
+        write(&alice, "bye.md", "Bye!");
+        assert_eq!(remote.sync(&alice).status, SyncStatus::Pushed);
+        assert_eq!(read(&alice, "work/sig.md"), "Bob");
+
+        assert_eq!(remote.sync(&bob).status, SyncStatus::Pulled);
+        assert_eq!(read(&bob, "bye.md"), "Bye!");
+        assert_eq!(remote.sync(&bob).status, SyncStatus::UpToDate);
+    }
+
+    #[test]
+    fn reports_conflicts_until_settled() {
+        if !git_installed() {
+            return;
+        }
+        let remote = Remote::new();
+        let alice = remote.checkout("alice");
+        let bob = remote.checkout("bob");
+
+        write(&alice, "hello.md", "Hello!");
+        write(&alice, "other.md", "Other");
+        remote.sync(&alice);
+        remote.sync(&bob);
+
+        write(&alice, "hello.md", "Hi from Alice");
+        remote.sync(&alice);
+        write(&bob, "hello.md", "Hi from Bob");
+
+        let conflict = remote.sync(&bob);
+        assert_eq!(conflict.status, SyncStatus::Conflict);
+        assert_eq!(conflict.conflicts, vec!["hello.md".to_string()]);
+        let remote_head = conflict.remote_head.unwrap();
+        // Nothing was merged; Bob's version is still there
+        assert_eq!(read(&bob, "hello.md"), "Hi from Bob");
+        assert_eq!(remote.sync(&bob).remote_head, Some(remote_head.clone()));
+
+        let settled = remote
+            .try_sync(&bob, Some(ConflictStrategy::KeepRemote), Some(&remote_head))
+            .unwrap();
+        assert_eq!(settled.status, SyncStatus::Pushed);
+        assert_eq!(read(&bob, "hello.md"), "Hi from Alice");
+
+        assert_eq!(remote.sync(&alice).status, SyncStatus::Pulled);
+        assert_eq!(read(&alice, "hello.md"), "Hi from Alice");
+    }
+
+    #[test]
+    fn keep_local_wins_the_conflict() {
+        if !git_installed() {
+            return;
+        }
+        let remote = Remote::new();
+        let alice = remote.checkout("alice");
+        let bob = remote.checkout("bob");
+
+        write(&alice, "hello.md", "Hello!");
+        remote.sync(&alice);
+        remote.sync(&bob);
+        write(&alice, "hello.md", "Hi from Alice");
+        remote.sync(&alice);
+        write(&bob, "hello.md", "Hi from Bob");
+
+        let remote_head = remote.sync(&bob).remote_head;
+        remote
+            .try_sync(
+                &bob,
+                Some(ConflictStrategy::KeepLocal),
+                remote_head.as_deref(),
+            )
+            .unwrap();
+        remote.sync(&alice);
+        assert_eq!(read(&alice, "hello.md"), "Hi from Bob");
+    }
+
+    #[test]
+    fn rejects_unknown_remote_heads() {
+        if !git_installed() {
+            return;
+        }
+        let remote = Remote::new();
+        let alice = remote.checkout("alice");
+        write(&alice, "hello.md", "Hello!");
+
+        assert!(remote
+            .try_sync(
+                &alice,
+                Some(ConflictStrategy::KeepLocal),
+                Some("0123456789abcdef0123456789abcdef01234567"),
+            )
+            .is_err());
+    }
+}
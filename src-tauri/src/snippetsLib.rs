@@ -1,28 +1,715 @@
-#[tauri::command]
-pub fn get_snippets() -> Result<Vec<(String, String)>, String> {
-    use std::fs;
-    use std::io::Read;
-    use std::path::PathBuf;
+use crate::frecencyLib::{self, UsageKind};
+use crate::fuzzyLib;
+use crate::placeholdersLib;
+use notify::{Event, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+use walkdir::WalkDir;
+
+const DEFAULT_EXTENSION: &str = "txt";
+// An exact keyword hit always outranks fuzzy title matches
+const KEYWORD_MATCH_BONUS: i64 = 1_000;
+// Editors write a file in several steps; report them as one change
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
 
-    let snippet_dir = PathBuf::from("snippets");
-    let mut snippets = Vec::new();
+// Set from setup to `<app data dir>/snippets`
+static SNIPPET_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
 
-    if snippet_dir.exists() && snippet_dir.is_dir() {
-        for entry in fs::read_dir(snippet_dir).map_err(|e| e.to_string())? {
-            if let Ok(entry) = entry {
+pub fn snippet_dir() -> Result<PathBuf, String> {
+    SNIPPET_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "The snippet folder is not set up".to_string())
+}
+
+// Use `<data_dir>/snippets`, seeding it from the old working-directory folder once
+pub fn init(data_dir: PathBuf) {
+    let dir = data_dir.join("snippets");
+    if !dir.exists() && fs::create_dir_all(&dir).is_ok() {
+        if let Ok(entries) = fs::read_dir("snippets") {
+            for entry in entries.flatten() {
                 let path = entry.path();
                 if path.is_file() {
-                    if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
-                        let mut file = fs::File::open(&path).map_err(|e| e.to_string())?;
-                        let mut contents = String::new();
-                        file.read_to_string(&mut contents)
-                            .map_err(|e| e.to_string())?;
-                        snippets.push((name.to_string(), contents));
-                    }
+                    let _ = fs::copy(&path, dir.join(entry.file_name()));
                 }
             }
         }
     }
+    *SNIPPET_DIR.lock().unwrap() = Some(dir);
+}
+
+// Snippet ids are paths relative to the snippet dir without the extension,
+// e.g. "signature" or "work/signature"; the folders are the categories.
+fn snippet_id(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?.with_extension("");
+    let parts: Vec<&str> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<_>>()?;
+    Some(parts.join("/"))
+}
+
+fn is_snippet_file(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_none_or(|n| n.starts_with('.'));
+    path.is_file() && !hidden
+}
+
+fn snippet_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| is_snippet_file(e.path()))
+        .filter_map(|e| Some((snippet_id(dir, e.path())?, e.into_path())))
+        .collect()
+}
+
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum Tags {
+    #[default]
+    None,
+    List(Vec<String>),
+    // "a, b, c"
+    Joined(String),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FrontMatter {
+    title: Option<String>,
+    #[serde(alias = "trigger")]
+    keyword: Option<String>,
+    tags: Tags,
+    description: Option<String>,
+    language: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FrontMatterOut<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyword: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'a str>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snippet {
+    // Path relative to the snippet dir without extension, e.g. "work/signature"
+    pub id: String,
+    pub title: String,
+    pub keyword: Option<String>,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub category: Option<String>,
+    // The body, without front matter
+    pub content: String,
+}
+
+// Splits `---` YAML or `+++` TOML front matter from the body
+fn split_front_matter(raw: &str) -> Option<(FrontMatter, &str)> {
+    let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+    let fence = ["---", "+++"]
+        .into_iter()
+        .find(|fence| raw.lines().next().map(str::trim_end) == Some(*fence))?;
+
+    let header_start = raw.find('\n')? + 1;
+    let mut offset = header_start;
+    for line in raw[header_start..].split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let header = &raw[header_start..offset];
+            let body = &raw[offset + line.len()..];
+            let front_matter = if fence == "---" {
+                serde_yaml_ng::from_str(header).ok()?
+            } else {
+                toml::from_str(header).ok()?
+            };
+            return Some((front_matter, body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+pub fn parse_snippet(id: &str, raw: &str) -> Snippet {
+    // Malformed front matter is kept as part of the body rather than dropped
+    let (front_matter, body) = split_front_matter(raw).unwrap_or((FrontMatter::default(), raw));
+
+    let tags = match front_matter.tags {
+        Tags::None => Vec::new(),
+        Tags::List(tags) => tags,
+        Tags::Joined(tags) => tags.split(',').map(|t| t.trim().to_string()).collect(),
+    };
+    let (category, stem) = match id.rsplit_once('/') {
+        Some((category, stem)) => (Some(category.to_string()), stem),
+        None => (None, id),
+    };
+
+    Snippet {
+        id: id.to_string(),
+        title: front_matter.title.unwrap_or_else(|| stem.to_string()),
+        keyword: front_matter.keyword.filter(|k| !k.is_empty()),
+        tags: tags.into_iter().filter(|t| !t.is_empty()).collect(),
+        description: front_matter.description,
+        language: front_matter.language,
+        category,
+        content: body.to_string(),
+    }
+}
+
+/// Renders a snippet back to file contents, with YAML front matter when it
+/// has metadata beyond what the file name already says.
+pub fn render_snippet_file(snippet: &Snippet) -> Result<String, String> {
+    let stem = snippet.id.rsplit('/').next().unwrap_or(&snippet.id);
+    let front_matter = FrontMatterOut {
+        title: Some(snippet.title.as_str()).filter(|title| *title != stem),
+        keyword: snippet.keyword.as_deref(),
+        tags: &snippet.tags,
+        description: snippet.description.as_deref(),
+        language: snippet.language.as_deref(),
+    };
+
+    let header = serde_yaml_ng::to_string(&front_matter).map_err(|e| e.to_string())?;
+    if header.trim() == "{}" {
+        Ok(snippet.content.clone())
+    } else {
+        Ok(format!("---\n{}---\n{}", header, snippet.content))
+    }
+}
+
+// Rejects ids that would escape the snippet dir
+pub fn validate_id(id: &str) -> Result<(), String> {
+    let path = Path::new(id);
+    let valid = !id.trim().is_empty()
+        && !id.contains('\\')
+        && path.components().all(
+            |c| matches!(c, Component::Normal(part) if !part.to_string_lossy().starts_with('.')),
+        );
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid snippet name '{}'", id))
+    }
+}
+
+fn is_visible(dir: &Path, path: &Path) -> bool {
+    path.strip_prefix(dir).map_or(true, |relative| {
+        !relative
+            .components()
+            .any(|part| part.as_os_str().to_string_lossy().starts_with('.'))
+    })
+}
+
+// The snippet files under one folder
+pub struct Library {
+    dir: PathBuf,
+}
+
+impl Library {
+    pub fn new(dir: PathBuf) -> Self {
+        Library { dir }
+    }
+
+    // The folder set up by `init`
+    pub fn open() -> Result<Self, String> {
+        snippet_dir().map(Library::new)
+    }
+
+    pub fn load(&self) -> Result<Vec<Snippet>, String> {
+        let mut snippets = Vec::new();
+        if self.dir.is_dir() {
+            for (id, path) in snippet_files(&self.dir) {
+                let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+                snippets.push(parse_snippet(&id, &contents));
+            }
+        }
+        Ok(snippets)
+    }
+
+    fn find(&self, id: &str) -> Result<PathBuf, String> {
+        snippet_files(&self.dir)
+            .into_iter()
+            .find(|(snippet, _)| snippet == id)
+            .map(|(_, path)| path)
+            .ok_or_else(|| format!("Snippet '{}' not found", id))
+    }
+
+    // Looks a snippet up by id, falling back to its keyword
+    pub fn get(&self, name: &str) -> Result<Snippet, String> {
+        if let Ok(path) = self.find(name) {
+            let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            return Ok(parse_snippet(name, &contents));
+        }
+        self.by_keyword(name)
+            .map_err(|_| format!("Snippet '{}' not found", name))
+    }
+
+    // Looks a snippet up by keyword only, ignoring ids
+    pub fn by_keyword(&self, keyword: &str) -> Result<Snippet, String> {
+        self.load()?
+            .into_iter()
+            .find(|snippet| snippet.keyword.as_deref() == Some(keyword))
+            .ok_or_else(|| format!("No snippet has the keyword '{}'", keyword))
+    }
+
+    // The snippet body, as used by `{snippet}` includes
+    pub fn read(&self, name: &str) -> Result<String, String> {
+        self.get(name).map(|snippet| snippet.content)
+    }
+
+    pub fn exists(&self, id: &str) -> bool {
+        self.find(id).is_ok()
+    }
+
+    fn ensure_available(&self, id: &str) -> Result<(), String> {
+        if self.exists(id) {
+            Err(format!("Snippet '{}' already exists", id))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Drop category folders left empty after `removed` was moved or deleted
+    fn remove_empty_categories(&self, removed: &Path) {
+        let mut parent = removed.parent();
+        while let Some(folder) = parent {
+            if folder == self.dir || fs::remove_dir(folder).is_err() {
+                break;
+            }
+            parent = folder.parent();
+        }
+    }
+
+    pub fn categories(&self) -> Vec<String> {
+        WalkDir::new(&self.dir)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_dir())
+            .filter_map(|e| snippet_id(&self.dir, e.path()))
+            .collect()
+    }
+
+    // `category` may be nested ("work/clients"); folders are created as needed
+    pub fn create(
+        &self,
+        name: &str,
+        contents: &str,
+        category: Option<&str>,
+    ) -> Result<String, String> {
+        let id = match category.map(|c| c.trim_matches('/')) {
+            Some(category) if !category.is_empty() => format!("{}/{}", category, name),
+            _ => name.to_string(),
+        };
+        validate_id(&id)?;
+        self.ensure_available(&id)?;
+
+        let path = self.dir.join(format!("{}.{}", id, DEFAULT_EXTENSION));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&path, contents).map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+
+    pub fn update(&self, name: &str, contents: &str) -> Result<(), String> {
+        let path = self.find(name)?;
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        let path = self.find(name)?;
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+        self.remove_empty_categories(&path);
+        Ok(())
+    }
+
+    // A new name with a category path moves the snippet there
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<String, String> {
+        validate_id(new_name)?;
+        let path = self.find(name)?;
+        if name == new_name {
+            return Ok(new_name.to_string());
+        }
+        self.ensure_available(new_name)?;
 
-    Ok(snippets)
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or(DEFAULT_EXTENSION);
+        let target = self.dir.join(format!("{}.{}", new_name, extension));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::rename(&path, &target).map_err(|e| e.to_string())?;
+        self.remove_empty_categories(&path);
+        Ok(new_name.to_string())
+    }
+
+    pub fn arguments(&self, name: &str) -> Result<Vec<placeholdersLib::Argument>, String> {
+        placeholdersLib::arguments(&self.read(name)?, &|name| self.read(name))
+    }
+
+    pub fn expand(
+        &self,
+        name: &str,
+        args: &HashMap<String, String>,
+    ) -> Result<(Snippet, placeholdersLib::Expansion), String> {
+        let snippet = self.get(name)?;
+        let expansion = placeholdersLib::expand(&snippet.content, args, &|name| self.read(name))?;
+        Ok((snippet, expansion))
+    }
+
+    // Calls `on_change` in the background once edits to the folder settle for
+    // `quiet`. Hidden entries such as a sync `.git` folder are ignored, and so
+    // are changes made while `on_change` runs, so its own writes don't retrigger it.
+    pub fn watch(
+        self,
+        quiet: Duration,
+        mut on_change: impl FnMut() + Send + 'static,
+    ) -> Result<(), String> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+        watcher
+            .watch(&self.dir, RecursiveMode::Recursive)
+            .map_err(|e| e.to_string())?;
+
+        std::thread::spawn(move || {
+            // Events stop when the watcher is dropped
+            let _watcher = watcher;
+            while let Ok(event) = rx.recv() {
+                let relevant = event.is_ok_and(|event: Event| {
+                    !event.kind.is_access() && event.paths.iter().any(|p| is_visible(&self.dir, p))
+                });
+                if !relevant {
+                    continue;
+                }
+                while rx.recv_timeout(quiet).is_ok() {}
+                on_change();
+                while rx.try_recv().is_ok() {}
+            }
+        });
+        Ok(())
+    }
+}
+
+pub fn load_snippets() -> Result<Vec<Snippet>, String> {
+    Library::open()?.load()
+}
+
+#[tauri::command]
+pub fn get_snippets() -> Result<Vec<Snippet>, String> {
+    Ok(frecencyLib::sort_by_frecency(
+        UsageKind::Snippet,
+        load_snippets()?,
+        |snippet| snippet.id.as_str(),
+    ))
+}
+
+// Ranks snippets by keyword, title, tags and description, optionally within one tag
+#[tauri::command]
+pub fn search_snippets(query: &str, tag: Option<String>) -> Result<Vec<Snippet>, String> {
+    let query = query.trim();
+    let mut ranked: Vec<(i64, Snippet)> = load_snippets()?
+        .into_iter()
+        .filter(|snippet| match &tag {
+            Some(tag) => snippet.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            None => true,
+        })
+        .filter_map(|snippet| {
+            let keyword_hit = snippet
+                .keyword
+                .as_deref()
+                .is_some_and(|k| k.eq_ignore_ascii_case(query));
+            let fields = [
+                Some(snippet.title.as_str()),
+                Some(snippet.id.as_str()),
+                snippet.keyword.as_deref(),
+                snippet.description.as_deref(),
+            ];
+            let best = fields
+                .into_iter()
+                .flatten()
+                .chain(snippet.tags.iter().map(String::as_str))
+                .filter_map(|field| fuzzyLib::fuzzy_match(query, field))
+                .map(|m| m.score)
+                .max();
+
+            let score = match (keyword_hit, best) {
+                (true, best) => KEYWORD_MATCH_BONUS + best.unwrap_or(0),
+                (false, Some(best)) => best,
+                (false, None) => return None,
+            };
+            Some((
+                score + frecencyLib::rank_boost(UsageKind::Snippet, &snippet.id),
+                snippet,
+            ))
+        })
+        .collect();
+
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    Ok(ranked.into_iter().map(|(_, snippet)| snippet).collect())
+}
+
+#[tauri::command]
+pub fn get_snippet_tags() -> Result<Vec<String>, String> {
+    let mut tags: Vec<String> = load_snippets()?
+        .into_iter()
+        .flat_map(|snippet| snippet.tags)
+        .collect();
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    Ok(tags)
+}
+
+#[tauri::command]
+pub fn get_snippet_categories() -> Result<Vec<String>, String> {
+    Ok(Library::open()?.categories())
+}
+
+#[tauri::command]
+pub fn create_snippet(
+    name: &str,
+    contents: &str,
+    category: Option<String>,
+) -> Result<String, String> {
+    Library::open()?.create(name, contents, category.as_deref())
+}
+
+#[tauri::command]
+pub fn update_snippet(name: &str, contents: &str) -> Result<(), String> {
+    Library::open()?.update(name, contents)
+}
+
+#[tauri::command]
+pub fn delete_snippet(name: &str) -> Result<(), String> {
+    Library::open()?.delete(name)
+}
+
+// Renames a snippet; a new name with a category path moves it there
+#[tauri::command]
+pub fn rename_snippet(name: &str, new_name: &str) -> Result<String, String> {
+    Library::open()?.rename(name, new_name)
+}
+
+// Lists the `{argument}` prompts the frontend must fill in before expanding
+#[tauri::command]
+pub fn get_snippet_arguments(name: &str) -> Result<Vec<placeholdersLib::Argument>, String> {
+    Library::open()?.arguments(name)
+}
+
+#[tauri::command]
+pub fn expand_snippet(
+    name: &str,
+    args: Option<HashMap<String, String>>,
+) -> Result<placeholdersLib::Expansion, String> {
+    let (snippet, expansion) = Library::open()?.expand(name, &args.unwrap_or_default())?;
+    frecencyLib::track(UsageKind::Snippet, &snippet.id);
+    Ok(expansion)
+}
+
+// Emit `snippets-changed` when files are edited outside the app
+pub fn watch(app: tauri::AppHandle) -> Result<(), String> {
+    Library::open()?.watch(WATCH_DEBOUNCE, move || {
+        let _ = app.emit("snippets-changed", ());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempLibrary(Library);
+
+    impl TempLibrary {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("snippets-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            TempLibrary(Library::new(dir))
+        }
+    }
+
+    impl Drop for TempLibrary {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0.dir);
+        }
+    }
+
+    #[test]
+    fn reads_yaml_and_toml_front_matter() {
+        let yaml = parse_snippet(
+            "work/reply",
+            "\u{feff}---\ntitle: Reply\ntrigger: \";re\"\ntags: a, b,\n---\nThanks!\n",
+        );
+        assert_eq!(yaml.title, "Reply");
+        assert_eq!(yaml.keyword.as_deref(), Some(";re"));
+        assert_eq!(yaml.tags, ["a", "b"]);
+        assert_eq!(yaml.category.as_deref(), Some("work"));
+        assert_eq!(yaml.content, "Thanks!\n");
+
+        let toml = parse_snippet(
+            "hi",
+            "+++\ntags = [\"x\"]\nlanguage = \"rust\"\n+++\nfn main() {}",
+        );
+        assert_eq!(toml.title, "hi");
+        assert_eq!(toml.tags, ["x"]);
+        assert_eq!(toml.language.as_deref(), Some("rust"));
+        assert_eq!(toml.content, "fn main() {}");
+    }
+
+    #[test]
+    fn keeps_malformed_or_unclosed_front_matter_in_the_body() {
+        for raw in ["---\ntitle: [oops\n---\nbody", "---\ntitle: Open\nbody"] {
+            let snippet = parse_snippet("plain", raw);
+            assert_eq!(snippet.title, "plain");
+            assert_eq!(snippet.content, raw);
+        }
+    }
+
+    #[test]
+    fn front_matter_round_trips() {
+        let snippet = Snippet {
+            id: "work/signature".into(),
+            title: "Work signature".into(),
+            keyword: Some(";sig".into()),
+            tags: vec!["email".into(), "work".into()],
+            description: Some("Sign-off: formal".into()),
+            language: None,
+            category: Some("work".into()),
+            content: "---\nRegards\n".into(),
+        };
+        let parsed = parse_snippet(&snippet.id, &render_snippet_file(&snippet).unwrap());
+        assert_eq!(parsed.title, snippet.title);
+        assert_eq!(parsed.keyword, snippet.keyword);
+        assert_eq!(parsed.tags, snippet.tags);
+        assert_eq!(parsed.description, snippet.description);
+        assert_eq!(parsed.language, None);
+        assert_eq!(parsed.content, snippet.content);
+
+        // Nothing beyond the file name needs no front matter at all
+        let plain = parse_snippet("plain", "Just text");
+        assert_eq!(render_snippet_file(&plain).unwrap(), "Just text");
+    }
+
+    #[test]
+    fn rejects_ids_outside_the_snippet_dir() {
+        for id in [
+            "",
+            " ",
+            "../escape",
+            "/etc/passwd",
+            "work/../../x",
+            ".hidden",
+            "work/.git/x",
+            "a\\b",
+        ] {
+            assert!(validate_id(id).is_err(), "{:?} should be rejected", id);
+        }
+        for id in ["signature", "work/clients/signature", "with space"] {
+            assert!(validate_id(id).is_ok(), "{:?} should be accepted", id);
+        }
+    }
+
+    #[test]
+    fn creates_updates_renames_and_deletes_snippets() {
+        let temp = TempLibrary::new();
+        let library = &temp.0;
+
+        let id = library
+            .create(
+                "hello",
+                "---\nkeyword: \";hi\"\n---\nHello",
+                Some("/greetings/"),
+            )
+            .unwrap();
+        assert_eq!(id, "greetings/hello");
+        assert!(library.dir.join("greetings/hello.txt").is_file());
+        assert!(library.create("hello", "again", Some("greetings")).is_err());
+        assert!(library.create("../hello", "escape", None).is_err());
+        assert_eq!(library.categories(), ["greetings"]);
+
+        library.update(&id, "Hi there").unwrap();
+        assert_eq!(library.read(&id).unwrap(), "Hi there");
+        assert!(library.by_keyword(";hi").is_err());
+
+        library.create("bye", "Bye", None).unwrap();
+        assert!(library.rename("bye", "greetings/hello").is_err());
+        assert_eq!(library.rename(&id, "work/hello").unwrap(), "work/hello");
+        assert!(!library.exists(&id));
+        assert!(!library.dir.join("greetings").exists());
+        assert_eq!(library.get("work/hello").unwrap().content, "Hi there");
+
+        library.delete("work/hello").unwrap();
+        assert!(!library.dir.join("work").exists());
+        assert!(library.delete("work/hello").is_err());
+        let ids: Vec<String> = library.load().unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, ["bye"]);
+    }
+
+    #[test]
+    fn finds_snippets_by_keyword_and_expands_includes() {
+        let temp = TempLibrary::new();
+        let library = &temp.0;
+        library
+            .create("sig", "Regards, {argument name=\"Sender\"}", None)
+            .unwrap();
+        library
+            .create(
+                "reply",
+                "---\nkeyword: \";re\"\n---\nThanks!\n{snippet name=\"sig\"}",
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(library.get(";re").unwrap().id, "reply");
+        let names: Vec<String> = library
+            .arguments("reply")
+            .unwrap()
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, ["Sender"]);
+        let args = HashMap::from([("Sender".to_string(), "Ann".to_string())]);
+        let (snippet, expansion) = library.expand(";re", &args).unwrap();
+        assert_eq!(snippet.id, "reply");
+        assert_eq!(expansion.text, "Thanks!\nRegards, Ann");
+    }
+
+    #[test]
+    fn watch_reports_visible_edits_only() {
+        let temp = TempLibrary::new();
+        let dir = temp.0.dir.clone();
+        fs::create_dir(dir.join(".git")).unwrap();
+        let (tx, rx) = mpsc::channel();
+        Library::new(dir.clone())
+            .watch(Duration::from_millis(50), move || {
+                let _ = tx.send(());
+            })
+            .unwrap();
+
+        fs::write(dir.join(".git/index"), "hidden").unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+        fs::write(dir.join("new.txt"), "visible").unwrap();
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
 }
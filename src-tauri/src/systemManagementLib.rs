@@ -1,176 +1,674 @@
-use std::process::Command;
+#[cfg(not(target_os = "linux"))]
+use crate::commandLib::CommandRunner;
+use crate::platformLib;
+use crate::platformLib::{BrightnessControl, PowerControl};
+#[cfg(target_os = "linux")]
+use crate::trashLib;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Emitter;
+
+// Brightness through `brightness` on macOS and WMI on Windows; Linux uses
+// `backlightLib::Backlight`
+#[cfg(not(target_os = "linux"))]
+pub struct SystemBrightness {
+    runner: Arc<dyn CommandRunner>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl SystemBrightness {
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        SystemBrightness { runner }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl BrightnessControl for SystemBrightness {
+    fn brightness(&self) -> Result<u8, String> {
+        #[cfg(target_os = "macos")]
+        {
+            // e.g. "display 0: brightness 0.750000"
+            let output = self.runner.output("brightness", &["-l"])?;
+            let level = output
+                .lines()
+                .filter(|line| line.contains("brightness"))
+                .find_map(|line| line.split_whitespace().last()?.parse::<f64>().ok())
+                .ok_or("No display reports its brightness")?;
+            Ok((level * 100.0).round() as u8)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            // One value per monitor that supports it; the first is the built-in panel
+            let script = "(Get-WmiObject -Namespace root/WMI -Class WmiMonitorBrightness).CurrentBrightness | Select-Object -First 1";
+            let output = self
+                .runner
+                .output("powershell", &["-Command", script])
+                .map_err(|e| format!("Failed to read brightness on Windows: {}", e))?;
+            output
+                .trim()
+                .parse()
+                .map_err(|_| "This display doesn't report its brightness".to_string())
+        }
+    }
+
+    fn set_brightness(&self, percent: u8) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        {
+            let level = (percent as f64 / 100.0).to_string();
+            self.runner
+                .output("brightness", &[&level])
+                .map(|_| ())
+                .map_err(|e| format!("Failed to set brightness on macOS: {}", e))
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let script = format!(
+                "(Get-WmiObject -Namespace root/WMI -Class WmiMonitorBrightnessMethods).WmiSetBrightness(1,{})",
+                percent
+            );
+            self.runner
+                .output("powershell", &["-Command", &script])
+                .map(|_| ())
+                .map_err(|e| format!("Failed to set brightness on Windows: {}", e))
+        }
+    }
+}
+
+// Power through AppleScript and `pmset` on macOS and `shutdown` on Windows;
+// Linux uses `logindLib::Logind`
+#[cfg(not(target_os = "linux"))]
+pub struct SystemPower {
+    runner: Arc<dyn CommandRunner>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl SystemPower {
+    pub fn new(runner: Arc<dyn CommandRunner>) -> Self {
+        SystemPower { runner }
+    }
+
+    fn run(&self, program: &str, args: &[&str], action: &str) -> Result<(), String> {
+        self.runner
+            .output(program, args)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to {}: {}", action, e))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl PowerControl for SystemPower {
+    fn shutdown(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let (program, args) = (
+            "osascript",
+            ["-e", "tell app \"System Events\" to shut down"],
+        );
+        #[cfg(target_os = "windows")]
+        let (program, args) = ("shutdown", ["/s", "/t", "0"]);
+
+        self.run(program, &args, "shut down")
+    }
+
+    fn restart(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let (program, args) = ("osascript", ["-e", "tell app \"System Events\" to restart"]);
+        #[cfg(target_os = "windows")]
+        let (program, args) = ("shutdown", ["/r", "/t", "0"]);
+
+        self.run(program, &args, "restart")
+    }
+
+    fn lock(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let (program, args) = (
+            "/System/Library/CoreServices/Menu Extras/User.menu/Contents/Resources/CGSession",
+            ["-suspend"],
+        );
+        #[cfg(target_os = "windows")]
+        let (program, args) = ("rundll32", ["user32.dll,LockWorkStation"]);
+
+        self.run(program, &args, "lock the screen")
+    }
+
+    fn suspend(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let (program, args) = ("pmset", ["sleepnow"].as_slice());
+        // Hibernates instead when hibernation is turned on
+        #[cfg(target_os = "windows")]
+        let (program, args) = (
+            "rundll32",
+            ["powrprof.dll,SetSuspendState", "0,1,0"].as_slice(),
+        );
+
+        self.run(program, args, "suspend")
+    }
+
+    fn hibernate(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        {
+            Err("macOS doesn't support hibernating on demand".into())
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            self.run("shutdown", &["/h"], "hibernate")
+        }
+    }
+
+    fn log_out(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        let (program, args) = (
+            "osascript",
+            ["-e", "tell app \"System Events\" to log out"].as_slice(),
+        );
+        #[cfg(target_os = "windows")]
+        let (program, args) = ("shutdown", ["/l"].as_slice());
+
+        self.run(program, args, "log out")
+    }
+}
 
 #[tauri::command]
 pub fn set_brightness(brightness: u8) -> Result<(), String> {
     if brightness > 100 {
         return Err("Brightness must be between 0 and 100".into());
     }
+    platformLib::current().brightness.set_brightness(brightness)
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        let level = brightness as f64 / 100.0;
-        let status = Command::new("brightness")
-            .arg(&level.to_string())
-            .status()
-            .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn get_brightness() -> Result<u8, String> {
+    platformLib::current().brightness.brightness()
+}
 
-        if status.success() {
-            Ok(())
-        } else {
-            Err("Failed to set brightness on macOS".into())
+// Moves the brightness by `delta` percent and returns the level read back.
+// A coarse backlight can round a small step back to where it was, so the
+// step grows until the level moves or reaches the end of the range.
+fn change_brightness(control: &dyn BrightnessControl, delta: i16) -> Result<u8, String> {
+    let current = control.brightness()?;
+    let mut target = current as i16 + delta;
+    loop {
+        let level = target.clamp(0, 100) as u8;
+        control.set_brightness(level)?;
+        let reached = control.brightness()?;
+        if reached != current || delta == 0 || level == 0 || level == 100 {
+            return Ok(reached);
         }
+        target += delta.signum();
     }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        let script = format!(
-            "powershell (Get-WmiObject -Namespace root/WMI -Class WmiMonitorBrightnessMethods).WmiSetBrightness(1,{})",
-            brightness
-        );
+#[tauri::command]
+pub fn increase_brightness(delta: u8) -> Result<u8, String> {
+    change_brightness(platformLib::current().brightness.as_ref(), delta as i16)
+}
 
-        let status = Command::new("powershell")
-            .args(&["-Command", &script])
-            .status()
-            .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn decrease_brightness(delta: u8) -> Result<u8, String> {
+    change_brightness(platformLib::current().brightness.as_ref(), -(delta as i16))
+}
 
-        if status.success() {
-            Ok(())
-        } else {
-            Err("Failed to set brightness on Windows".into())
-        }
+// Seconds before a shutdown or restart when the caller doesn't choose
+const DEFAULT_COUNTDOWN: u64 = 10;
+// Countdown events go out every second from here on, every minute before
+const COUNTDOWN_EVERY_SECOND: u64 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerAction {
+    Shutdown,
+    Restart,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PendingAction {
+    pub id: u64,
+    pub action: PowerAction,
+    // Unix time in seconds
+    pub execute_at: i64,
+    pub remaining: u64,
+}
+
+// At most one action is pending; scheduling another replaces it
+static PENDING: Mutex<Option<PendingAction>> = Mutex::new(None);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// What a pending action reports while it counts down
+enum PowerEvent {
+    Pending(PendingAction),
+    Failed(String),
+}
+
+fn emitter(app: tauri::AppHandle) -> impl Fn(PowerEvent) + Send + 'static {
+    move |event| {
+        let _ = match event {
+            PowerEvent::Pending(pending) => app.emit("pending-action", &pending),
+            PowerEvent::Failed(e) => app.emit("pending-action-failed", e),
+        };
     }
 }
 
-#[tauri::command]
-pub fn increase_brightness(delta: u8) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        let output = Command::new("brightness")
-            .arg("-l")
-            .output()
-            .map_err(|e| e.to_string())?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let current_level = stdout
-            .lines()
-            .find_map(|line| {
-                if line.contains("brightness") {
-                    line.split_whitespace()
-                        .last()
-                        .and_then(|v| v.parse::<f64>().ok())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0.5);
-
-        let new_level = (current_level + (delta as f64 / 100.0)).min(1.0);
-        set_brightness((new_level * 100.0) as u8)
+fn run_power_action(power: &dyn PowerControl, action: PowerAction) -> Result<(), String> {
+    match action {
+        PowerAction::Shutdown => power.shutdown(),
+        PowerAction::Restart => power.restart(),
     }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        Err("Incremental brightness on Windows not implemented".into())
+// Runs `action` after `delay` seconds, reporting `Pending` as it counts down
+// and `Failed` if it can't be carried out. The wall clock is used so a sleep
+// in between doesn't push the action back.
+// `% 60` rather than `is_multiple_of` keeps this building on older toolchains.
+#[allow(clippy::manual_is_multiple_of)]
+fn schedule_power_action_in(
+    power: Arc<dyn PowerControl>,
+    notify: impl Fn(PowerEvent) + Send + 'static,
+    action: PowerAction,
+    delay: u64,
+) -> Result<Option<PendingAction>, String> {
+    if delay == 0 {
+        PENDING.lock().unwrap().take();
+        return run_power_action(&*power, action).map(|_| None);
     }
+
+    let pending = PendingAction {
+        id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+        action,
+        execute_at: Local::now().timestamp() + delay as i64,
+        remaining: delay,
+    };
+    *PENDING.lock().unwrap() = Some(pending.clone());
+    notify(PowerEvent::Pending(pending.clone()));
+
+    let id = pending.id;
+    let execute_at = pending.execute_at;
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        let remaining = (execute_at - Local::now().timestamp()).max(0) as u64;
+
+        let mut guard = PENDING.lock().unwrap();
+        // Cancelled or replaced by a newer action
+        let Some(current) = guard.as_mut().filter(|p| p.id == id) else {
+            return;
+        };
+        current.remaining = remaining;
+        if remaining == 0 {
+            guard.take();
+            drop(guard);
+            if let Err(e) = run_power_action(&*power, action) {
+                notify(PowerEvent::Failed(e));
+            }
+            return;
+        }
+        let update = current.clone();
+        drop(guard);
+        if remaining <= COUNTDOWN_EVERY_SECOND || remaining % 60 == 0 {
+            notify(PowerEvent::Pending(update));
+        }
+    });
+    Ok(Some(pending))
 }
 
+// Restarts after `delay_seconds` (10 by default, 0 for right away); returns
+// the pending action so the UI can offer to cancel it
 #[tauri::command]
-pub fn decrease_brightness(delta: u8) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        let output = Command::new("brightness")
-            .arg("-l")
-            .output()
-            .map_err(|e| e.to_string())?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let current_level = stdout
-            .lines()
-            .find_map(|line| {
-                if line.contains("brightness") {
-                    line.split_whitespace()
-                        .last()
-                        .and_then(|v| v.parse::<f64>().ok())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0.5);
-
-        let new_level = (current_level - (delta as f64 / 100.0)).max(0.0);
-        set_brightness((new_level * 100.0) as u8)
+pub fn restart_system(
+    app: tauri::AppHandle,
+    delay_seconds: Option<u64>,
+) -> Result<Option<PendingAction>, String> {
+    schedule_power_action_in(
+        platformLib::current().power.clone(),
+        emitter(app),
+        PowerAction::Restart,
+        delay_seconds.unwrap_or(DEFAULT_COUNTDOWN),
+    )
+}
+
+#[tauri::command]
+pub fn shutdown_system(
+    app: tauri::AppHandle,
+    delay_seconds: Option<u64>,
+) -> Result<Option<PendingAction>, String> {
+    schedule_power_action_in(
+        platformLib::current().power.clone(),
+        emitter(app),
+        PowerAction::Shutdown,
+        delay_seconds.unwrap_or(DEFAULT_COUNTDOWN),
+    )
+}
+
+// The next time after `now` that the clock shows `time` ("23:00" or "23:00:30")
+fn next_occurrence<Tz: TimeZone>(time: &str, now: &DateTime<Tz>) -> Result<DateTime<Tz>, String> {
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", time))?;
+    let tomorrow = time <= now.time();
+    let mut date = now.date_naive();
+    if tomorrow {
+        date = date.succ_opt().ok_or("Invalid date")?;
     }
+    date.and_time(time)
+        .and_local_timezone(now.timezone())
+        .earliest()
+        .ok_or_else(|| {
+            format!(
+                "{} doesn't exist {} because the clocks change",
+                time.format("%H:%M"),
+                if tomorrow { "tomorrow" } else { "today" }
+            )
+        })
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        Err("Decremental brightness on Windows not implemented".into())
+// Schedules `action` for the next time the clock shows `time` ("23:00")
+#[tauri::command]
+pub fn schedule_power_action(
+    app: tauri::AppHandle,
+    action: PowerAction,
+    time: &str,
+) -> Result<PendingAction, String> {
+    let now = Local::now();
+    let at = next_occurrence(time, &now)?;
+    let delay = (at - now).num_seconds().max(1) as u64;
+    schedule_power_action_in(
+        platformLib::current().power.clone(),
+        emitter(app),
+        action,
+        delay,
+    )?
+    .ok_or_else(|| "Nothing was scheduled".into())
+}
+
+#[tauri::command]
+pub fn get_pending_action() -> Option<PendingAction> {
+    PENDING.lock().unwrap().clone()
+}
+
+fn cancel() -> Option<PendingAction> {
+    PENDING.lock().unwrap().take()
+}
+
+// Cancels the pending shutdown or restart, emitting `pending-action-cancelled`
+#[tauri::command]
+pub fn cancel_pending_action(app: tauri::AppHandle) -> Option<PendingAction> {
+    let cancelled = cancel();
+    if let Some(pending) = &cancelled {
+        let _ = app.emit("pending-action-cancelled", pending);
     }
+    cancelled
+}
+
+#[tauri::command]
+pub fn lock_system() -> Result<(), String> {
+    platformLib::current().power.lock()
 }
 
 #[tauri::command]
-pub fn restart_system() {
+pub fn suspend_system() -> Result<(), String> {
+    platformLib::current().power.suspend()
+}
+
+#[tauri::command]
+pub fn hibernate_system() -> Result<(), String> {
+    platformLib::current().power.hibernate()
+}
+
+#[tauri::command]
+pub fn logout_system() -> Result<(), String> {
+    platformLib::current().power.log_out()
+}
+
+// Empties the trash; on Linux `older_than_days` keeps recently deleted items
+#[tauri::command]
+pub fn empty_trash(older_than_days: Option<u64>) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
-        let _ = std::process::Command::new("osascript")
-            .args(&["-e", "tell app \"System Events\" to restart"])
-            .spawn();
+        if older_than_days.is_some() {
+            return Err("Emptying by age is only supported on Linux.".into());
+        }
+        platformLib::current()
+            .runner
+            .output(
+                "osascript",
+                &["-e", "tell application \"Finder\" to empty the trash"],
+            )
+            .map(|_| ())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        trashLib::empty(older_than_days).map(|_| ())
     }
-
     #[cfg(target_os = "windows")]
     {
-        let _ = std::process::Command::new("shutdown")
-            .args(&["/r", "/t", "0"])
-            .spawn();
+        let _ = older_than_days;
+        Err("This command only works on macOS and Linux.".into())
     }
 }
 
+#[derive(Serialize, Clone, Debug)]
+pub struct TrashItem {
+    // Location inside the trash; pass it to `restore_from_trash`
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    // As written in the info file, local time like "2024-05-01T09:30:00"
+    pub deleted_at: Option<String>,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
 #[tauri::command]
-pub fn shutdown_system() {
-    #[cfg(target_os = "macos")]
+pub fn list_trash() -> Result<Vec<TrashItem>, String> {
+    #[cfg(target_os = "linux")]
     {
-        let _ = std::process::Command::new("osascript")
-            .args(&["-e", "tell app \"System Events\" to shut down"])
-            .spawn();
+        Ok(trashLib::list())
     }
-
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "linux"))]
     {
-        let _ = std::process::Command::new("shutdown")
-            .args(&["/s", "/t", "0"])
-            .spawn();
+        Err("Listing the trash is only supported on Linux.".into())
     }
 }
 
+// Puts a `list_trash` item back at its original path and returns that path
 #[tauri::command]
-pub fn lock_system() {
-    #[cfg(target_os = "macos")]
+pub fn restore_from_trash(id: &str) -> Result<String, String> {
+    #[cfg(target_os = "linux")]
     {
-        let _ = std::process::Command::new(
-            "/System/Library/CoreServices/Menu Extras/User.menu/Contents/Resources/CGSession",
-        )
-        .arg("-suspend")
-        .spawn();
+        trashLib::restore(id).map(|path| path.display().to_string())
     }
-
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "linux"))]
     {
-        let _ = std::process::Command::new("rundll32")
-            .args(&["user32.dll,LockWorkStation"])
-            .spawn();
+        let _ = id;
+        Err("Restoring from the trash is only supported on Linux.".into())
     }
 }
 
+// Bytes used by the trash
 #[tauri::command]
-pub fn empty_trash() -> Result<(), String> {
-    #[cfg(target_os = "macos")]
+pub fn trash_size() -> Result<u64, String> {
+    #[cfg(target_os = "linux")]
     {
-        std::process::Command::new("osascript")
-            .arg("-e")
-            .arg("tell application \"Finder\" to empty the trash")
-            .output()
-            .map_err(|e| e.to_string())?;
-        Ok(())
-    }
-    #[cfg(not(target_os = "macos"))]
+        Ok(trashLib::size())
+    }
+    #[cfg(not(target_os = "linux"))]
     {
-        Err("This command only works on macOS.".into())
+        Err("Measuring the trash is only supported on Linux.".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use std::sync::mpsc;
+
+    // Tests that schedule share the one pending slot
+    static PENDING_LOCK: Mutex<()> = Mutex::new(());
+
+    #[derive(Default)]
+    struct Power {
+        done: Mutex<Vec<&'static str>>,
+    }
+
+    impl Power {
+        fn record(&self, action: &'static str) -> Result<(), String> {
+            self.done.lock().unwrap().push(action);
+            Ok(())
+        }
+    }
+
+    impl PowerControl for Power {
+        fn shutdown(&self) -> Result<(), String> {
+            self.record("shutdown")
+        }
+        fn restart(&self) -> Result<(), String> {
+            self.record("restart")
+        }
+        fn lock(&self) -> Result<(), String> {
+            self.record("lock")
+        }
+        fn suspend(&self) -> Result<(), String> {
+            self.record("suspend")
+        }
+        fn hibernate(&self) -> Result<(), String> {
+            self.record("hibernate")
+        }
+        fn log_out(&self) -> Result<(), String> {
+            self.record("log_out")
+        }
+    }
+
+    fn schedule(
+        power: &Arc<Power>,
+        action: PowerAction,
+        delay: u64,
+    ) -> (Option<PendingAction>, mpsc::Receiver<u64>) {
+        let (tx, rx) = mpsc::channel();
+        let notify = move |event| {
+            if let PowerEvent::Pending(pending) = event {
+                let _ = tx.send(pending.remaining);
+            }
+        };
+        let pending = schedule_power_action_in(power.clone(), notify, action, delay).unwrap();
+        (pending, rx)
+    }
+
+    #[test]
+    fn runs_a_scheduled_action_after_counting_down() {
+        let _lock = PENDING_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let power = Arc::new(Power::default());
+        let (pending, rx) = schedule(&power, PowerAction::Restart, 2);
+        let pending = pending.unwrap();
+        assert_eq!(pending.remaining, 2);
+        assert_eq!(get_pending_action().map(|p| p.id), Some(pending.id));
+
+        let remaining: Vec<u64> = rx.iter().collect();
+        assert_eq!(remaining.first(), Some(&2));
+        assert!(remaining.len() >= 2, "{:?}", remaining);
+        assert_eq!(*power.done.lock().unwrap(), ["restart"]);
+        assert!(get_pending_action().is_none());
+    }
+
+    #[test]
+    fn cancelled_or_replaced_actions_never_run() {
+        let _lock = PENDING_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let power = Arc::new(Power::default());
+        let (first, _) = schedule(&power, PowerAction::Shutdown, 1);
+        let (second, _) = schedule(&power, PowerAction::Restart, 2);
+        assert_eq!(cancel().map(|p| p.id), second.map(|p| p.id));
+        assert!(first.is_some());
+        std::thread::sleep(Duration::from_millis(2500));
+        assert!(power.done.lock().unwrap().is_empty());
+        assert!(cancel().is_none());
+    }
+
+    #[test]
+    fn a_zero_delay_runs_right_away() {
+        let _lock = PENDING_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let power = Arc::new(Power::default());
+        let (pending, _) = schedule(&power, PowerAction::Shutdown, 0);
+        assert!(pending.is_none());
+        assert_eq!(*power.done.lock().unwrap(), ["shutdown"]);
+    }
+
+    #[test]
+    fn scheduled_times_roll_over_to_tomorrow() {
+        let zone = FixedOffset::east_opt(3600).unwrap();
+        let now = zone.with_ymd_and_hms(2026, 12, 31, 22, 30, 0).unwrap();
+        assert_eq!(
+            next_occurrence("23:00", &now).unwrap(),
+            zone.with_ymd_and_hms(2026, 12, 31, 23, 0, 0).unwrap()
+        );
+        assert_eq!(
+            next_occurrence("22:30", &now).unwrap(),
+            zone.with_ymd_and_hms(2027, 1, 1, 22, 30, 0).unwrap()
+        );
+        assert_eq!(
+            next_occurrence("07:15:30", &now).unwrap(),
+            zone.with_ymd_and_hms(2027, 1, 1, 7, 15, 30).unwrap()
+        );
+        for bad in ["25:00", "7pm", ""] {
+            assert_eq!(
+                next_occurrence(bad, &now).unwrap_err(),
+                format!("Invalid time '{}', expected HH:MM", bad)
+            );
+        }
+    }
+
+    // A backlight with `max` raw steps, rounding like the sysfs one
+    struct Steps {
+        max: u32,
+        raw: Mutex<u32>,
+    }
+
+    impl Steps {
+        fn new(max: u32, raw: u32) -> Self {
+            Steps {
+                max,
+                raw: Mutex::new(raw),
+            }
+        }
+    }
+
+    impl BrightnessControl for Steps {
+        fn brightness(&self) -> Result<u8, String> {
+            let raw = *self.raw.lock().unwrap() as f64;
+            Ok((raw * 100.0 / self.max as f64).round() as u8)
+        }
+
+        fn set_brightness(&self, percent: u8) -> Result<(), String> {
+            *self.raw.lock().unwrap() = (percent as f64 * self.max as f64 / 100.0).round() as u32;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn brightness_steps_report_the_level_reached() {
+        let fine = Steps::new(96000, 48000);
+        assert_eq!(change_brightness(&fine, 5).unwrap(), 55);
+        assert_eq!(change_brightness(&fine, -60).unwrap(), 0);
+        assert_eq!(change_brightness(&fine, 120).unwrap(), 100);
+    }
+
+    #[test]
+    fn coarse_brightness_moves_at_least_one_raw_step() {
+        let coarse = Steps::new(7, 4);
+        assert_eq!(change_brightness(&coarse, 5).unwrap(), 71);
+        assert_eq!(*coarse.raw.lock().unwrap(), 5);
+        assert_eq!(change_brightness(&coarse, -5).unwrap(), 57);
+        assert_eq!(*coarse.raw.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn brightness_stops_at_the_ends_of_the_range() {
+        let top = Steps::new(7, 7);
+        assert_eq!(change_brightness(&top, 5).unwrap(), 100);
+        let bottom = Steps::new(7, 0);
+        assert_eq!(change_brightness(&bottom, -5).unwrap(), 0);
     }
 }
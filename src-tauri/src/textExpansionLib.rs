@@ -0,0 +1,693 @@
+// System-wide snippet expansion on X11: typing a snippet keyword such as
+// ";sig" in any application replaces it with the expanded snippet.
+//
+// Keystrokes are observed through the RECORD extension and the replacement
+// is typed through XTEST, so this works under Xvfb without a window manager.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Only the tail of what was typed can ever match a keyword
+const BUFFER_LEN: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ExpansionSettings {
+    enabled: bool,
+    // WM_CLASS names (instance or class) of windows where expansion is off
+    excluded_apps: Vec<String>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ExpansionSettings {
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+static SETTINGS: Lazy<Mutex<ExpansionSettings>> =
+    Lazy::new(|| Mutex::new(ExpansionSettings::default()));
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Tracks recently typed characters and finds keywords at their end.
+#[derive(Default)]
+pub struct TriggerBuffer {
+    typed: Vec<char>,
+}
+
+impl TriggerBuffer {
+    pub fn push(&mut self, c: char) {
+        self.typed.push(c);
+        if self.typed.len() > BUFFER_LEN {
+            self.typed.remove(0);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+    }
+
+    pub fn clear(&mut self) {
+        self.typed.clear();
+    }
+
+    /// The longest keyword the typed text currently ends with.
+    pub fn matching<'a>(&self, keywords: &'a [String]) -> Option<&'a str> {
+        keywords
+            .iter()
+            .filter(|keyword| {
+                let keyword: Vec<char> = keyword.chars().collect();
+                !keyword.is_empty() && self.typed.ends_with(&keyword)
+            })
+            .max_by_key(|keyword| keyword.chars().count())
+            .map(String::as_str)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::{TriggerBuffer, SETTINGS};
+    use crate::placeholdersLib;
+    use crate::snippetsLib::Library;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::record::{self, ConnectionExt as _};
+    use x11rb::protocol::xproto::{self, AtomEnum, ConnectionExt as _, Keycode, Keysym, Window};
+    use x11rb::protocol::xtest::ConnectionExt as _;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    // Snippet keywords are re-read at most this often while typing
+    const KEYWORD_REFRESH: Duration = Duration::from_secs(2);
+    // Some clients drop synthetic keys that arrive too quickly
+    const KEY_DELAY: Duration = Duration::from_millis(2);
+
+    const XK_BACKSPACE: Keysym = 0xff08;
+    const XK_TAB: Keysym = 0xff09;
+    const XK_RETURN: Keysym = 0xff0d;
+    const XK_LEFT: Keysym = 0xff51;
+    const XK_SHIFT_L: Keysym = 0xffe1;
+    const XK_SHIFT_R: Keysym = 0xffe2;
+    const XK_CONTROL_L: Keysym = 0xffe3;
+    const XK_CONTROL_R: Keysym = 0xffe4;
+    const XK_CAPS_LOCK: Keysym = 0xffe5;
+    const XK_ALT_L: Keysym = 0xffe9;
+    const XK_SUPER_R: Keysym = 0xffec;
+
+    // The running daemon's control connection and RECORD context, so `stop`
+    // disables it on the display `run` was given
+    struct Recording {
+        conn: Arc<RustConnection>,
+        context: record::Context,
+    }
+
+    static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+    fn keysym_to_char(keysym: Keysym) -> Option<char> {
+        match keysym {
+            0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+            0x0100_0000..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+            _ => None,
+        }
+    }
+
+    fn char_to_keysym(c: char) -> Keysym {
+        match c {
+            '\n' => XK_RETURN,
+            '\t' => XK_TAB,
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as Keysym,
+            _ => 0x0100_0000 | c as Keysym,
+        }
+    }
+
+    struct Keymap {
+        min_keycode: Keycode,
+        per_keycode: usize,
+        keysyms: Vec<Keysym>,
+    }
+
+    impl Keymap {
+        fn load(conn: &RustConnection) -> Result<Self, String> {
+            let setup = conn.setup();
+            let (min, max) = (setup.min_keycode, setup.max_keycode);
+            let reply = conn
+                .get_keyboard_mapping(min, max - min + 1)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?;
+            Ok(Keymap {
+                min_keycode: min,
+                per_keycode: reply.keysyms_per_keycode as usize,
+                keysyms: reply.keysyms,
+            })
+        }
+
+        fn keysym(&self, keycode: Keycode, index: usize) -> Keysym {
+            let start = (keycode - self.min_keycode) as usize * self.per_keycode;
+            let sym = self.keysyms.get(start + index).copied().unwrap_or(0);
+            // A lone lowercase keysym implies its uppercase form with Shift
+            if index == 1 && sym == 0 {
+                let base = self.keysym(keycode, 0);
+                return keysym_to_char(base)
+                    .and_then(|c| c.to_uppercase().next())
+                    .map_or(0, char_to_keysym);
+            }
+            sym
+        }
+
+        // (keycode, needs shift) that produces `keysym`
+        fn find(&self, keysym: Keysym) -> Option<(Keycode, bool)> {
+            let count = self.keysyms.len() / self.per_keycode.max(1);
+            (0..count).find_map(|i| {
+                let keycode = self.min_keycode + i as Keycode;
+                if self.keysym(keycode, 0) == keysym {
+                    Some((keycode, false))
+                } else if self.per_keycode > 1 && self.keysym(keycode, 1) == keysym {
+                    Some((keycode, true))
+                } else {
+                    None
+                }
+            })
+        }
+
+        // A keycode with no symbols, borrowed to type characters not on the keyboard
+        fn spare_keycode(&self) -> Option<Keycode> {
+            self.keysyms
+                .chunks(self.per_keycode.max(1))
+                .rposition(|syms| syms.iter().all(|&s| s == 0))
+                .map(|i| self.min_keycode + i as Keycode)
+        }
+    }
+
+    fn focused_window_classes(conn: &RustConnection, root: Window) -> Vec<String> {
+        let active = (|| {
+            let atom = conn
+                .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+                .ok()?
+                .reply()
+                .ok()?
+                .atom;
+            let reply = conn
+                .get_property(false, root, atom, AtomEnum::WINDOW, 0, 1)
+                .ok()?
+                .reply()
+                .ok()?;
+            let window = reply.value32()?.next();
+            window
+        })()
+        .or_else(|| Some(conn.get_input_focus().ok()?.reply().ok()?.focus));
+
+        let Some(window) = active else {
+            return Vec::new();
+        };
+        conn.get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| {
+                reply
+                    .value
+                    .split(|&b| b == 0)
+                    .filter(|part| !part.is_empty())
+                    .map(|part| String::from_utf8_lossy(part).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    struct Typist<'a> {
+        conn: &'a RustConnection,
+        root: Window,
+        keymap: &'a Keymap,
+        // Synthetic (event type, keycode) pairs sent, which RECORD will echo back to us
+        sent: Vec<(u8, Keycode)>,
+    }
+
+    impl Typist<'_> {
+        fn key(&mut self, keycode: Keycode, press: bool) -> Result<(), String> {
+            let kind = if press {
+                xproto::KEY_PRESS_EVENT
+            } else {
+                xproto::KEY_RELEASE_EVENT
+            };
+            self.conn
+                .xtest_fake_input(kind, keycode, x11rb::CURRENT_TIME, self.root, 0, 0, 0)
+                .map_err(|e| e.to_string())?;
+            self.sent.push((kind, keycode));
+            Ok(())
+        }
+
+        fn tap(&mut self, keycode: Keycode, shift: bool) -> Result<(), String> {
+            let shift_keycode = self.keymap.find(XK_SHIFT_L).map(|(k, _)| k);
+            if let (true, Some(shift_keycode)) = (shift, shift_keycode) {
+                self.key(shift_keycode, true)?;
+                self.key(keycode, true)?;
+                self.key(keycode, false)?;
+                self.key(shift_keycode, false)?;
+            } else {
+                self.key(keycode, true)?;
+                self.key(keycode, false)?;
+            }
+            self.conn.sync().map_err(|e| e.to_string())?;
+            std::thread::sleep(KEY_DELAY);
+            Ok(())
+        }
+
+        fn tap_keysym(&mut self, keysym: Keysym) -> Result<(), String> {
+            if let Some((keycode, shift)) = self.keymap.find(keysym) {
+                return self.tap(keycode, shift);
+            }
+
+            // Temporarily bind the keysym to a spare keycode, as xdotool does
+            let spare = self
+                .keymap
+                .spare_keycode()
+                .ok_or("No spare keycode to type an unmapped character")?;
+            let per = self.keymap.per_keycode as u8;
+            let bound = vec![keysym; per as usize];
+            self.conn
+                .change_keyboard_mapping(1, spare, per, &bound)
+                .map_err(|e| e.to_string())?;
+            self.conn.sync().map_err(|e| e.to_string())?;
+            self.tap(spare, false)?;
+            self.conn
+                .change_keyboard_mapping(1, spare, per, &vec![0; per as usize])
+                .map_err(|e| e.to_string())?;
+            self.conn.sync().map_err(|e| e.to_string())
+        }
+
+        fn replace(
+            &mut self,
+            erase: usize,
+            text: &str,
+            cursor: Option<usize>,
+        ) -> Result<(), String> {
+            for _ in 0..erase {
+                self.tap_keysym(XK_BACKSPACE)?;
+            }
+            for c in text.chars().filter(|&c| c != '\r') {
+                self.tap_keysym(char_to_keysym(c))?;
+            }
+            if let Some(cursor) = cursor {
+                let back = text.chars().skip(cursor).filter(|&c| c != '\r').count();
+                for _ in 0..back {
+                    self.tap_keysym(XK_LEFT)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn load_keywords(snippets: &Library) -> Vec<String> {
+        snippets
+            .load()
+            .map(|snippets| snippets.into_iter().filter_map(|s| s.keyword).collect())
+            .unwrap_or_default()
+    }
+
+    fn is_excluded(conn: &RustConnection, root: Window) -> bool {
+        let excluded = SETTINGS.lock().unwrap().excluded_apps.clone();
+        if excluded.is_empty() {
+            return false;
+        }
+        focused_window_classes(conn, root)
+            .iter()
+            .any(|class| excluded.iter().any(|app| app.eq_ignore_ascii_case(class)))
+    }
+
+    /// Runs the expansion loop until `stop` is called or the display goes away.
+    ///
+    /// `display` is an X display name such as ":99"; `None` uses `$DISPLAY`.
+    /// Keywords are looked up in `snippets`, and the loop also ends once
+    /// `running` is cleared.
+    pub fn run(
+        display: Option<&str>,
+        snippets: &Library,
+        running: &AtomicBool,
+    ) -> Result<(), String> {
+        let (ctrl, screen) = x11rb::connect(display).map_err(|e| e.to_string())?;
+        let ctrl = Arc::new(ctrl);
+        let (data, _) = x11rb::connect(display).map_err(|e| e.to_string())?;
+        for extension in [record::X11_EXTENSION_NAME, "XTEST"] {
+            if ctrl
+                .extension_information(extension)
+                .map_err(|e| e.to_string())?
+                .is_none()
+            {
+                return Err(format!("The X server lacks the {} extension", extension));
+            }
+        }
+
+        let root = ctrl.setup().roots[screen].root;
+        let mut keymap = Keymap::load(&ctrl)?;
+
+        let context = ctrl.generate_id().map_err(|e| e.to_string())?;
+        let range = record::Range {
+            device_events: record::Range8 {
+                first: xproto::KEY_PRESS_EVENT,
+                last: xproto::KEY_RELEASE_EVENT,
+            },
+            ..Default::default()
+        };
+        ctrl.record_create_context(context, 0, &[record::CS::ALL_CLIENTS.into()], &[range])
+            .map_err(|e| e.to_string())?
+            .check()
+            .map_err(|e| e.to_string())?;
+        *RECORDING.lock().unwrap() = Some(Recording {
+            conn: ctrl.clone(),
+            context,
+        });
+        // `stop` may have run before the context existed
+        if !running.load(Ordering::SeqCst) {
+            RECORDING.lock().unwrap().take();
+            let _ = ctrl.record_free_context(context);
+            let _ = ctrl.flush();
+            return Ok(());
+        }
+
+        let mut buffer = TriggerBuffer::default();
+        let mut keywords = load_keywords(snippets);
+        let mut keywords_loaded = Instant::now();
+        let (mut shift, mut control, mut alt, mut caps_lock) = (false, false, false, false);
+        // Our own keys still to come back; anything else is the user typing
+        let mut echoes: VecDeque<(u8, Keycode)> = VecDeque::new();
+
+        let replies = data
+            .record_enable_context(context)
+            .map_err(|e| e.to_string())?;
+        for reply in replies {
+            let reply = reply.map_err(|e| e.to_string())?;
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            // Category 0 is "from server"; the rest are start/end markers
+            if reply.category != 0 {
+                continue;
+            }
+
+            for event in reply.data.chunks_exact(32) {
+                let (kind, keycode) = (event[0] & 0x7f, event[1]);
+                let press = kind == xproto::KEY_PRESS_EVENT;
+                if echoes.front() == Some(&(kind, keycode)) {
+                    echoes.pop_front();
+                    continue;
+                }
+
+                let base = keymap.keysym(keycode, 0);
+                match base {
+                    XK_SHIFT_L | XK_SHIFT_R => shift = press,
+                    XK_CONTROL_L | XK_CONTROL_R => control = press,
+                    XK_CAPS_LOCK if press => caps_lock = !caps_lock,
+                    XK_ALT_L..=XK_SUPER_R => alt = press,
+                    _ if !press => {}
+                    XK_BACKSPACE => buffer.backspace(),
+                    _ if control || alt => buffer.clear(),
+                    _ => {
+                        let keysym = keymap.keysym(keycode, shift as usize);
+                        let Some(mut c) = keysym_to_char(keysym) else {
+                            // Enter, arrows, Escape... move away from what was typed
+                            buffer.clear();
+                            continue;
+                        };
+                        if caps_lock && c.is_alphabetic() {
+                            c = if shift {
+                                c.to_lowercase().next().unwrap_or(c)
+                            } else {
+                                c.to_uppercase().next().unwrap_or(c)
+                            };
+                        }
+                        buffer.push(c);
+
+                        if keywords_loaded.elapsed() > KEYWORD_REFRESH {
+                            keywords = load_keywords(snippets);
+                            keywords_loaded = Instant::now();
+                        }
+                        let Some(keyword) = buffer.matching(&keywords).map(String::from) else {
+                            continue;
+                        };
+                        buffer.clear();
+                        if is_excluded(&ctrl, root) {
+                            continue;
+                        }
+
+                        // Snippets needing arguments can't be prompted for here
+                        let Ok(expansion) = snippets.by_keyword(&keyword).and_then(|snippet| {
+                            placeholdersLib::expand(&snippet.content, &HashMap::new(), &|name| {
+                                snippets.read(name)
+                            })
+                        }) else {
+                            continue;
+                        };
+
+                        keymap = Keymap::load(&ctrl).unwrap_or(keymap);
+                        let mut typist = Typist {
+                            conn: &ctrl,
+                            root,
+                            keymap: &keymap,
+                            sent: Vec::new(),
+                        };
+                        let result = typist.replace(
+                            keyword.chars().count(),
+                            &expansion.text,
+                            expansion.cursor,
+                        );
+                        echoes.extend(typist.sent);
+                        if let Err(e) = result {
+                            eprintln!("Snippet expansion failed: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+
+        RECORDING.lock().unwrap().take();
+        let _ = ctrl.record_free_context(context);
+        let _ = ctrl.flush();
+        Ok(())
+    }
+
+    pub fn stop() {
+        // Disabling the context ends the blocking reply stream in `run`
+        if let Some(recording) = RECORDING.lock().unwrap().take() {
+            let _ = recording.conn.record_disable_context(recording.context);
+            let _ = recording.conn.flush();
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::xvfbTestLib::Xvfb;
+        use std::sync::Arc;
+        use x11rb::protocol::xproto::{CreateWindowAux, EventMask, InputFocus, WindowClass};
+        use x11rb::protocol::Event;
+
+        #[test]
+        fn expands_keywords_typed_under_xvfb() {
+            let Some(xvfb) = Xvfb::start() else {
+                eprintln!("Xvfb is not installed; skipping");
+                return;
+            };
+            let display = xvfb.display.clone();
+
+            let dir = std::env::temp_dir().join(format!("snippets-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            // The trigger is also another snippet's id; only the keyword counts
+            std::fs::write(dir.join(";hi.txt"), "Wrong").unwrap();
+            std::fs::write(dir.join("hello.txt"), "---\nkeyword: \";hi\"\n---\nHello").unwrap();
+
+            let running = Arc::new(AtomicBool::new(true));
+            let daemon = {
+                let (display, dir, running) = (display.clone(), dir.clone(), running.clone());
+                std::thread::spawn(move || run(Some(&display), &Library::new(dir), &running))
+            };
+            let started = Instant::now();
+            while RECORDING.lock().unwrap().is_none() && started.elapsed() < Duration::from_secs(5)
+            {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            std::thread::sleep(Duration::from_millis(200));
+
+            let (conn, screen) = x11rb::connect(Some(&display)).unwrap();
+            let root = conn.setup().roots[screen].root;
+            let window = conn.generate_id().unwrap();
+            conn.create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                100,
+                100,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                0,
+                &CreateWindowAux::new().event_mask(EventMask::KEY_PRESS),
+            )
+            .unwrap();
+            conn.map_window(window).unwrap();
+            conn.sync().unwrap();
+            conn.set_input_focus(InputFocus::PARENT, window, x11rb::CURRENT_TIME)
+                .unwrap();
+            conn.sync().unwrap();
+
+            let keymap = Keymap::load(&conn).unwrap();
+            let mut typist = Typist {
+                conn: &conn,
+                root,
+                keymap: &keymap,
+                sent: Vec::new(),
+            };
+            typist.replace(0, ";hi", None).unwrap();
+
+            // Replay what the window received, backspaces included
+            let mut text = String::new();
+            let started = Instant::now();
+            while text != "Hello" && started.elapsed() < Duration::from_secs(5) {
+                match conn.poll_for_event().unwrap() {
+                    Some(Event::KeyPress(key)) => {
+                        let shifted =
+                            u16::from(key.state) & u16::from(xproto::KeyButMask::SHIFT) != 0;
+                        match keymap.keysym(key.detail, shifted as usize) {
+                            XK_BACKSPACE => {
+                                text.pop();
+                            }
+                            keysym => text.extend(keysym_to_char(keysym)),
+                        }
+                    }
+                    Some(_) => {}
+                    None => std::thread::sleep(Duration::from_millis(10)),
+                }
+            }
+
+            running.store(false, Ordering::SeqCst);
+            stop();
+            let _ = daemon.join();
+            drop(xvfb);
+            let _ = std::fs::remove_dir_all(&dir);
+            assert_eq!(text, "Hello");
+        }
+    }
+}
+
+fn start() -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        let snippets = crate::snippetsLib::Library::open()?;
+        if RUNNING.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        std::thread::spawn(move || {
+            if let Err(e) = x11::run(None, &snippets, &RUNNING) {
+                eprintln!("Text expansion stopped: {}", e);
+            }
+            RUNNING.store(false, Ordering::SeqCst);
+        });
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err("System-wide snippet expansion is only supported on Linux (X11).".into())
+    }
+}
+
+fn stop() {
+    if !RUNNING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    x11::stop();
+}
+
+// Load settings and start the daemon if it was enabled; called from setup
+pub fn init(data_dir: PathBuf) {
+    let path = data_dir.join("text_expansion.json");
+    let mut settings: ExpansionSettings = fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    settings.path = Some(path);
+    let enabled = settings.enabled;
+    *SETTINGS.lock().unwrap() = settings;
+
+    if enabled {
+        if let Err(e) = start() {
+            eprintln!("Failed to start text expansion: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_text_expansion_settings() -> ExpansionSettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_text_expansion_enabled(enabled: bool) -> Result<(), String> {
+    if enabled {
+        start()?;
+    } else {
+        stop();
+    }
+
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.enabled = enabled;
+    settings.save()
+}
+
+#[tauri::command]
+pub fn set_text_expansion_exclusions(apps: Vec<String>) -> Result<(), String> {
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.excluded_apps = apps;
+    settings.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_matches_the_longest_keyword_at_the_end() {
+        let keywords = vec![";s".to_string(), ";sig".to_string(), "sig".to_string()];
+        let mut buffer = TriggerBuffer::default();
+        for c in "x;sig".chars() {
+            buffer.push(c);
+        }
+        assert_eq!(buffer.matching(&keywords), Some(";sig"));
+
+        buffer.backspace();
+        assert_eq!(buffer.matching(&keywords), None);
+        buffer.backspace();
+        assert_eq!(buffer.matching(&keywords), Some(";s"));
+
+        buffer.clear();
+        assert_eq!(buffer.matching(&keywords), None);
+    }
+
+    #[test]
+    fn buffer_keeps_only_the_tail() {
+        let keywords = vec!["ab".to_string()];
+        let mut buffer = TriggerBuffer::default();
+        for _ in 0..BUFFER_LEN * 2 {
+            buffer.push('x');
+        }
+        buffer.push('a');
+        buffer.push('b');
+        assert_eq!(buffer.typed.len(), BUFFER_LEN);
+        assert_eq!(buffer.matching(&keywords), Some("ab"));
+    }
+}
@@ -0,0 +1,626 @@
+// Freedesktop.org Trash specification, used on Linux
+// https://specifications.freedesktop.org/trash-spec/trashspec-latest.html
+
+use crate::systemManagementLib::TrashItem;
+use crate::xdgLib;
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use std::ffi::CString;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+fn current_uid() -> Result<u32, String> {
+    fs::metadata("/proc/self")
+        .map(|m| m.uid())
+        .map_err(|e| e.to_string())
+}
+
+pub fn home_trash_dir() -> Result<PathBuf, String> {
+    xdgLib::data_home()
+        .map(|dir| dir.join("Trash"))
+        .ok_or_else(|| "HOME is not set".to_string())
+}
+
+// Trash folders are only accessible to their owner, as the spec asks
+fn create_private_dir(path: &Path) -> Result<(), String> {
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)
+        .map_err(|e| e.to_string())
+}
+
+// Walk up from `path` until the parent lives on another device
+fn mount_point(path: &Path) -> Result<PathBuf, String> {
+    let dev = fs::symlink_metadata(path).map_err(|e| e.to_string())?.dev();
+    let mut top = path.to_path_buf();
+    while let Some(parent) = top.parent() {
+        match fs::metadata(parent) {
+            Ok(meta) if meta.dev() == dev => top = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    Ok(top)
+}
+
+// `$topdir/.Trash/$uid` is only usable when `.Trash` is a real sticky directory
+fn shared_trash_dir(topdir: &Path, uid: u32) -> Option<PathBuf> {
+    let shared = topdir.join(".Trash");
+    let meta = fs::symlink_metadata(&shared).ok()?;
+    let sticky = meta.mode() & 0o1000 != 0;
+    if meta.is_dir() && !meta.file_type().is_symlink() && sticky {
+        Some(shared.join(uid.to_string()))
+    } else {
+        None
+    }
+}
+
+/// A trash directory together with the volume it serves.
+///
+/// `topdir` is `None` for the home trash, whose info files store absolute
+/// paths; per-volume trashes store paths relative to their `topdir`.
+pub struct TrashDir {
+    pub path: PathBuf,
+    pub topdir: Option<PathBuf>,
+}
+
+// Pick the trash directory for a file, per the spec's home/top-directory rules
+fn trash_dir_for(path: &Path) -> Result<TrashDir, String> {
+    let home_trash = home_trash_dir()?;
+    create_private_dir(&home_trash)?;
+
+    let file_dev = fs::symlink_metadata(path).map_err(|e| e.to_string())?.dev();
+    let home_dev = fs::metadata(&home_trash).map_err(|e| e.to_string())?.dev();
+    if file_dev == home_dev {
+        return Ok(TrashDir {
+            path: home_trash,
+            topdir: None,
+        });
+    }
+
+    let topdir = mount_point(path)?;
+    let uid = current_uid()?;
+    let trash =
+        shared_trash_dir(&topdir, uid).unwrap_or_else(|| topdir.join(format!(".Trash-{}", uid)));
+    Ok(TrashDir {
+        path: trash,
+        topdir: Some(topdir),
+    })
+}
+
+// Percent-encode each path segment, keeping the separators
+pub fn encode_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Reserve a unique name by creating its `.trashinfo` file exclusively
+fn reserve_info_file(info_dir: &Path, file_name: &str) -> Result<(String, fs::File), String> {
+    let (stem, ext) = match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (file_name.to_string(), String::new()),
+    };
+
+    for n in 1.. {
+        let candidate = if n == 1 {
+            file_name.to_string()
+        } else {
+            format!("{}.{}{}", stem, n, ext)
+        };
+        let info_path = info_dir.join(format!("{}.trashinfo", candidate));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => return Ok((candidate, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    unreachable!()
+}
+
+/// Moves `path` into the appropriate trash and returns its new location.
+pub fn trash_file(path: &Path) -> Result<PathBuf, String> {
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Cannot trash a path without a file name")?
+        .to_string();
+
+    let trash = trash_dir_for(&path)?;
+    let files_dir = trash.path.join("files");
+    let info_dir = trash.path.join("info");
+    create_private_dir(&files_dir)?;
+    create_private_dir(&info_dir)?;
+
+    let original = match &trash.topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path).to_path_buf(),
+        None => path.clone(),
+    };
+
+    let (name, mut info) = reserve_info_file(&info_dir, &file_name)?;
+    let info_path = info_dir.join(format!("{}.trashinfo", name));
+    let contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&original),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+    if let Err(e) = info.write_all(contents.as_bytes()) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e.to_string());
+    }
+
+    let destination = files_dir.join(&name);
+    if let Err(e) = fs::rename(&path, &destination) {
+        let _ = fs::remove_file(&info_path);
+        return Err(format!("Failed to move {} to trash: {}", path.display(), e));
+    }
+
+    Ok(destination)
+}
+
+// Undo the octal escapes (`\040` for a space) in /proc/mounts fields
+fn unescape_mount_field(field: &str) -> String {
+    let mut out = Vec::new();
+    let bytes = field.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match (bytes[i], octal) {
+            (b'\\', Some(byte)) => {
+                out.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Every existing trash directory: the home trash, then those on mounted volumes.
+pub fn trash_dirs() -> Vec<TrashDir> {
+    let mut dirs = Vec::new();
+    if let Ok(home) = home_trash_dir() {
+        if home.is_dir() {
+            dirs.push(TrashDir {
+                path: home,
+                topdir: None,
+            });
+        }
+    }
+
+    let Ok(uid) = current_uid() else {
+        return dirs;
+    };
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    for mount in mounts.lines().filter_map(|line| line.split(' ').nth(1)) {
+        let topdir = PathBuf::from(unescape_mount_field(mount));
+        let candidates = shared_trash_dir(&topdir, uid)
+            .into_iter()
+            .chain([topdir.join(format!(".Trash-{}", uid))]);
+        for path in candidates {
+            if path.is_dir() && !dirs.iter().any(|d: &TrashDir| d.path == path) {
+                dirs.push(TrashDir {
+                    path,
+                    topdir: Some(topdir.clone()),
+                });
+            }
+        }
+    }
+    dirs
+}
+
+struct TrashEntry {
+    item: TrashItem,
+    file: PathBuf,
+    info: PathBuf,
+    deleted_at: Option<NaiveDateTime>,
+}
+
+// Size on disk, not following symlinks
+fn disk_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+        + meta.len()
+}
+
+fn parse_info(contents: &str) -> (Option<String>, Option<String>) {
+    let mut path = None;
+    let mut date = None;
+    let mut in_section = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == "[Trash Info]";
+        } else if in_section {
+            if let Some(value) = line.strip_prefix("Path=") {
+                path = urlencoding::decode(value).ok().map(|p| p.into_owned());
+            } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                date = Some(value.to_string());
+            }
+        }
+    }
+    (path, date)
+}
+
+fn entries_in(trash: &TrashDir) -> Vec<TrashEntry> {
+    let info_dir = trash.path.join("info");
+    let Ok(infos) = fs::read_dir(&info_dir) else {
+        return Vec::new();
+    };
+    infos
+        .flatten()
+        .filter_map(|entry| {
+            let info = entry.path();
+            let name = info
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".trashinfo")?
+                .to_string();
+            let file = trash.path.join("files").join(&name);
+            let meta = fs::symlink_metadata(&file).ok()?;
+            let (path, date) = parse_info(&fs::read_to_string(&info).ok()?);
+            let path = PathBuf::from(path?);
+            let original = match &trash.topdir {
+                Some(topdir) if path.is_relative() => topdir.join(path),
+                _ => path,
+            };
+            let deleted_at = date
+                .as_deref()
+                .and_then(|d| NaiveDateTime::parse_from_str(d, "%Y-%m-%dT%H:%M:%S").ok());
+            Some(TrashEntry {
+                item: TrashItem {
+                    id: file.display().to_string(),
+                    name,
+                    original_path: original.display().to_string(),
+                    deleted_at: date,
+                    size: disk_size(&file),
+                    is_dir: meta.is_dir(),
+                },
+                file,
+                info,
+                deleted_at,
+            })
+        })
+        .collect()
+}
+
+fn all_entries() -> Vec<TrashEntry> {
+    trash_dirs().iter().flat_map(entries_in).collect()
+}
+
+/// Trashed items from every trash directory, most recently deleted first.
+pub fn list() -> Vec<TrashItem> {
+    let mut items: Vec<TrashItem> = all_entries().into_iter().map(|e| e.item).collect();
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    items
+}
+
+// Renames `from` to `to`, failing instead of replacing anything already there
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    let c_path = |path: &Path| CString::new(path.as_os_str().as_bytes());
+    let (from_c, to_c) = (c_path(from)?, c_path(to)?);
+    let renamed = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            from_c.as_ptr(),
+            libc::AT_FDCWD,
+            to_c.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    if renamed == 0 {
+        return Ok(());
+    }
+    let error = io::Error::last_os_error();
+    if !matches!(error.raw_os_error(), Some(libc::EINVAL | libc::ENOSYS)) {
+        return Err(error);
+    }
+
+    // The filesystem doesn't support the flag, so claim the name first
+    if fs::symlink_metadata(from)?.is_dir() {
+        fs::create_dir(to)?;
+        // Only ever replaces the empty directory created above
+        fs::rename(from, to).inspect_err(|_| {
+            let _ = fs::remove_dir(to);
+        })
+    } else {
+        fs::hard_link(from, to)?;
+        fs::remove_file(from)
+    }
+}
+
+/// Moves a trashed item back to where it was deleted from, never
+/// overwriting a file that has since taken its place.
+pub fn restore(id: &str) -> Result<PathBuf, String> {
+    let entry = all_entries()
+        .into_iter()
+        .find(|e| e.item.id == id)
+        .ok_or_else(|| format!("'{}' is not in the trash", id))?;
+    let original = PathBuf::from(&entry.item.original_path);
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    match rename_no_replace(&entry.file, &original) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(format!("{} already exists", original.display()))
+        }
+        Err(e) => return Err(format!("Failed to restore {}: {}", original.display(), e)),
+    }
+    let _ = fs::remove_file(&entry.info);
+    Ok(original)
+}
+
+fn size_of(trashes: &[TrashDir]) -> u64 {
+    trashes
+        .iter()
+        .flat_map(entries_in)
+        .map(|e| e.item.size)
+        .sum()
+}
+
+/// Total bytes taken by everything in the trash.
+pub fn size() -> u64 {
+    size_of(&trash_dirs())
+}
+
+fn remove_entry(file: &Path, info: &Path) -> Result<(), String> {
+    let is_dir = fs::symlink_metadata(file)
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    let removed = if is_dir {
+        fs::remove_dir_all(file)
+    } else {
+        fs::remove_file(file)
+    };
+    match removed {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to delete {}: {}", file.display(), e)),
+    }
+    // Remove the info file last so a failure never leaves an untracked file
+    let _ = fs::remove_file(info);
+    Ok(())
+}
+
+/// Permanently deletes trashed items, only those deleted more than
+/// `older_than_days` ago when given. Returns how many were removed.
+pub fn empty(older_than_days: Option<u64>) -> Result<usize, String> {
+    empty_in(&trash_dirs(), older_than_days)
+}
+
+fn empty_in(trashes: &[TrashDir], older_than_days: Option<u64>) -> Result<usize, String> {
+    let cutoff = older_than_days
+        .map(|days| {
+            i64::try_from(days)
+                .ok()
+                .and_then(TimeDelta::try_days)
+                .and_then(|age| Local::now().naive_local().checked_sub_signed(age))
+                .ok_or_else(|| format!("{} days is too long ago", days))
+        })
+        .transpose()?;
+    let mut removed = 0;
+    for trash in trashes {
+        for entry in entries_in(trash) {
+            let old_enough = match (cutoff, entry.deleted_at) {
+                (None, _) => true,
+                (Some(cutoff), Some(deleted_at)) => deleted_at < cutoff,
+                // Undated items are left alone when filtering by age
+                (Some(_), None) => false,
+            };
+            if old_enough {
+                remove_entry(&entry.file, &entry.info)?;
+                removed += 1;
+            }
+        }
+
+        if cutoff.is_none() {
+            // Files without an info file and the size cache go too
+            if let Ok(files) = fs::read_dir(trash.path.join("files")) {
+                for file in files.flatten() {
+                    let info = trash
+                        .path
+                        .join("info")
+                        .join(format!("{}.trashinfo", file.file_name().to_string_lossy()));
+                    remove_entry(&file.path(), &info)?;
+                }
+            }
+            let _ = fs::remove_file(trash.path.join("directorysizes"));
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::MutexGuard;
+
+    // A temp dir holding both the files to trash and `XDG_DATA_HOME`, so
+    // everything lands in its home trash
+    struct TempHome {
+        dir: PathBuf,
+        previous: Option<std::ffi::OsString>,
+        _env: MutexGuard<'static, ()>,
+    }
+
+    impl TempHome {
+        fn new() -> Self {
+            let env = xdgLib::ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let dir = std::env::temp_dir().join(format!("trash-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(dir.join("data")).unwrap();
+            let previous = std::env::var_os("XDG_DATA_HOME");
+            std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+            TempHome {
+                dir,
+                previous,
+                _env: env,
+            }
+        }
+
+        fn trash(&self) -> TrashDir {
+            TrashDir {
+                path: self.dir.join("data/Trash"),
+                topdir: None,
+            }
+        }
+
+        fn file(&self, name: &str, contents: &str) -> PathBuf {
+            let path = self.dir.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+
+        // Trash entry for a file deleted `days` ago
+        fn trashed(&self, name: &str, days: i64) {
+            let trash = self.trash().path;
+            fs::create_dir_all(trash.join("files")).unwrap();
+            fs::create_dir_all(trash.join("info")).unwrap();
+            fs::write(trash.join("files").join(name), "old").unwrap();
+            let date = Local::now().naive_local() - TimeDelta::days(days);
+            fs::write(
+                trash.join("info").join(format!("{}.trashinfo", name)),
+                format!(
+                    "[Trash Info]\nPath=/gone/{}\nDeletionDate={}\n",
+                    name,
+                    date.format("%Y-%m-%dT%H:%M:%S")
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    impl Drop for TempHome {
+        fn drop(&mut self) {
+            match &self.previous {
+                Some(previous) => std::env::set_var("XDG_DATA_HOME", previous),
+                None => std::env::remove_var("XDG_DATA_HOME"),
+            }
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn parses_info_files_and_decodes_paths() {
+        let (path, date) = parse_info(
+            "[Other]\nPath=/wrong\n[Trash Info]\nPath=/home/me/My%20Notes%25.txt\nDeletionDate=2024-01-02T03:04:05\n",
+        );
+        assert_eq!(path.as_deref(), Some("/home/me/My Notes%.txt"));
+        assert_eq!(date.as_deref(), Some("2024-01-02T03:04:05"));
+        assert_eq!(parse_info("Path=/outside/a/section"), (None, None));
+        assert_eq!(
+            encode_path(Path::new("/home/me/My Notes%.txt")),
+            "/home/me/My%20Notes%25.txt"
+        );
+    }
+
+    #[test]
+    fn trashes_and_restores_files() {
+        let home = TempHome::new();
+        let first = home.file("My Notes.txt", "one");
+        let trashed = trash_file(&first).unwrap();
+        home.file("My Notes.txt", "two");
+        let second = trash_file(&first).unwrap();
+        assert_eq!(trashed, home.trash().path.join("files/My Notes.txt"));
+        assert_eq!(second, home.trash().path.join("files/My Notes.2.txt"));
+        for dir in ["", "files", "info"] {
+            let mode = fs::metadata(home.trash().path.join(dir)).unwrap().mode();
+            assert_eq!(mode & 0o777, 0o700, "{}", dir);
+        }
+        let info =
+            fs::read_to_string(home.trash().path.join("info/My Notes.txt.trashinfo")).unwrap();
+        assert!(info.contains("My%20Notes.txt\n"), "{}", info);
+
+        let items: Vec<TrashItem> = entries_in(&home.trash())
+            .into_iter()
+            .map(|e| e.item)
+            .collect();
+        assert_eq!(items.len(), 2);
+        assert!(items
+            .iter()
+            .all(|item| item.original_path == first.display().to_string()));
+
+        assert_eq!(restore(&trashed.display().to_string()).unwrap(), first);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one");
+        assert!(!home
+            .trash()
+            .path
+            .join("info/My Notes.txt.trashinfo")
+            .exists());
+
+        // The second copy must not overwrite the restored one
+        let error = restore(&second.display().to_string()).unwrap_err();
+        assert!(error.contains("already exists"), "{}", error);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one");
+        assert!(second.exists());
+        assert_eq!(entries_in(&home.trash()).len(), 1);
+    }
+
+    #[test]
+    fn restores_directories_without_replacing_them() {
+        let home = TempHome::new();
+        let folder = home.dir.join("folder");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("inside.txt"), "x").unwrap();
+        let trashed = trash_file(&folder).unwrap();
+
+        fs::create_dir(&folder).unwrap();
+        assert!(restore(&trashed.display().to_string()).is_err());
+        fs::remove_dir(&folder).unwrap();
+        restore(&trashed.display().to_string()).unwrap();
+        assert_eq!(fs::read_to_string(folder.join("inside.txt")).unwrap(), "x");
+    }
+
+    #[test]
+    fn empties_by_age_and_reports_size() {
+        let home = TempHome::new();
+        home.trashed("recent.txt", 1);
+        home.trashed("old.txt", 40);
+        fs::write(home.trash().path.join("files/orphan"), "no info").unwrap();
+        let trashes = [home.trash()];
+        assert_eq!(size_of(&trashes), 6);
+
+        let error = empty_in(&trashes, Some(u64::MAX)).unwrap_err();
+        assert!(error.contains("too long ago"), "{}", error);
+        assert_eq!(empty_in(&trashes, Some(30)).unwrap(), 1);
+        let names: Vec<String> = entries_in(&home.trash())
+            .into_iter()
+            .map(|e| e.item.name)
+            .collect();
+        assert_eq!(names, ["recent.txt"]);
+
+        assert_eq!(empty_in(&trashes, None).unwrap(), 1);
+        assert_eq!(
+            fs::read_dir(home.trash().path.join("files"))
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(size_of(&trashes), 0);
+    }
+}
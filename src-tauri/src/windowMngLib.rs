@@ -1,5 +1,26 @@
+#[cfg(not(target_os = "linux"))]
+use crate::platformLib;
+use arboard::Clipboard;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::Duration;
 use tauri::Manager;
 
+// Window (X11 id, Windows handle) or application (macOS name) that had focus
+// before the launcher was shown, so pasted text can be sent back to it
+#[cfg(target_os = "linux")]
+type FocusedWindow = u32;
+#[cfg(target_os = "macos")]
+type FocusedWindow = String;
+#[cfg(target_os = "windows")]
+type FocusedWindow = isize;
+
+static PREVIOUS_WINDOW: Lazy<Mutex<Option<FocusedWindow>>> = Lazy::new(|| Mutex::new(None));
+
+// Time for the target window to take focus, and to read the clipboard
+const FOCUS_DELAY: Duration = Duration::from_millis(150);
+const PASTE_DELAY: Duration = Duration::from_millis(300);
+
 #[tauri::command]
 pub fn minimize_window(app: tauri::AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
@@ -46,8 +67,351 @@ pub fn close_window_command(window: tauri::Window) -> Result<(), String> {
 
 pub fn show_and_center_window(app: tauri::AppHandle) {
     if let Some(win) = app.get_webview_window("main") {
+        if !win.is_focused().unwrap_or(false) {
+            remember_previous_window();
+        }
         let _ = win.show();
         let _ = win.set_focus();
         let _ = win.center(); // This will center the window on the screen
     }
 }
+
+// Records the currently focused window as the target for pasting, replacing
+// any left from an earlier showing
+fn remember_previous_window() {
+    *PREVIOUS_WINDOW.lock().unwrap() = focused_window();
+}
+
+fn focused_window() -> Option<FocusedWindow> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::active_window(None).ok().flatten()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let name = platformLib::current()
+            .runner
+            .output(
+                "osascript",
+                &[
+                    "-e",
+                    "tell application \"System Events\" to get name of first application process whose frontmost is true",
+                ],
+            )
+            .ok()?;
+        (!name.is_empty()).then_some(name)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+        // Null when no window has focus
+        let window = unsafe { GetForegroundWindow() } as isize;
+        (window != 0).then_some(window)
+    }
+}
+
+fn focus_window(window: &FocusedWindow) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::activate(None, *window)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "tell application \"{}\" to activate",
+            window.replace('"', "\\\"")
+        );
+        platformLib::current()
+            .runner
+            .output("osascript", &["-e", &script])
+            .map(|_| ())
+            .map_err(|e| format!("Failed to activate {}: {}", window, e))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::HWND;
+        use windows_sys::Win32::UI::WindowsAndMessaging::{IsWindow, SetForegroundWindow};
+        let hwnd = *window as HWND;
+        // Both take any handle and fail on one whose window has closed.
+        // Windows lets this process move the focus since it had it last.
+        if unsafe { IsWindow(hwnd) } == 0 {
+            return Err("The previous window has been closed".into());
+        }
+        if unsafe { SetForegroundWindow(hwnd) } == 0 {
+            return Err("Failed to activate the previous window".into());
+        }
+        Ok(())
+    }
+}
+
+fn send_paste_shortcut() -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::send_paste(None)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        platformLib::current()
+            .runner
+            .output(
+                "osascript",
+                &[
+                    "-e",
+                    "tell application \"System Events\" to keystroke \"v\" using command down",
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Failed to send the paste shortcut: {}", e))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        platformLib::current()
+            .runner
+            .output(
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-Command",
+                    "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.SendKeys]::SendWait('^v')",
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Failed to send the paste shortcut: {}", e))
+    }
+}
+
+// Only text and images on the clipboard are put back afterwards; other
+// formats, such as copied files or rich text, are lost
+fn paste_text(text: &str) -> Result<(), String> {
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    let original_text = clipboard.get_text().ok();
+    let original_image = match original_text {
+        Some(_) => None,
+        None => clipboard.get_image().ok(),
+    };
+    clipboard.set_text(text).map_err(|e| e.to_string())?;
+
+    // Each showing of the launcher records its own target
+    let previous = PREVIOUS_WINDOW.lock().unwrap().take();
+    let result = previous
+        .as_ref()
+        .map_or(Ok(()), focus_window)
+        .and_then(|_| {
+            std::thread::sleep(FOCUS_DELAY);
+            send_paste_shortcut()
+        });
+
+    // Give the target app time to read the clipboard before restoring it
+    std::thread::sleep(PASTE_DELAY);
+    if let Some(original) = original_text {
+        let _ = clipboard.set_text(original);
+    } else if let Some(original) = original_image {
+        let _ = clipboard.set_image(original);
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn paste_into_previous_app(app: tauri::AppHandle, text: String) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+    tauri::async_runtime::spawn_blocking(move || paste_text(&text))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        self, AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, InputFocus, Keysym,
+        Window,
+    };
+    use x11rb::protocol::xtest::ConnectionExt as _;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    const XK_CONTROL_L: Keysym = 0xffe3;
+    const XK_V: Keysym = 0x76;
+
+    // `None` is the display from $DISPLAY
+    fn connect(display: Option<&str>) -> Result<(RustConnection, Window), String> {
+        let (conn, screen) = x11rb::connect(display).map_err(|e| e.to_string())?;
+        let root = conn.setup().roots[screen].root;
+        Ok((conn, root))
+    }
+
+    fn active_atom(conn: &RustConnection) -> Result<u32, String> {
+        Ok(conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?
+            .atom)
+    }
+
+    fn current_active(conn: &RustConnection, root: Window) -> Result<Option<Window>, String> {
+        let reply = conn
+            .get_property(false, root, active_atom(conn)?, AtomEnum::WINDOW, 0, 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        let window = reply.value32().and_then(|mut values| values.next());
+        Ok(window.filter(|&w| w != 0))
+    }
+
+    pub fn active_window(display: Option<&str>) -> Result<Option<Window>, String> {
+        let (conn, root) = connect(display)?;
+        current_active(&conn, root)
+    }
+
+    pub fn activate(display: Option<&str>, window: Window) -> Result<(), String> {
+        let (conn, root) = connect(display)?;
+        // Source indication 2 tells the window manager a pager asked for it,
+        // which focus-stealing prevention lets through
+        let event = ClientMessageEvent::new(32, window, active_atom(&conn)?, [2, 0, 0, 0, 0]);
+        conn.send_event(
+            false,
+            root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.sync().map_err(|e| e.to_string())?;
+
+        // Without an EWMH window manager nobody answers the request
+        std::thread::sleep(super::FOCUS_DELAY);
+        if current_active(&conn, root)? != Some(window) {
+            conn.set_input_focus(InputFocus::PARENT, window, x11rb::CURRENT_TIME)
+                .map_err(|e| e.to_string())?;
+            conn.sync().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn send_paste(display: Option<&str>) -> Result<(), String> {
+        let (conn, root) = connect(display)?;
+        let setup = conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        let per = (mapping.keysyms_per_keycode as usize).max(1);
+        let keycode = |keysym: Keysym| {
+            mapping
+                .keysyms
+                .chunks(per)
+                .position(|syms| syms.first() == Some(&keysym))
+                .map(|i| min + i as u8)
+                .ok_or_else(|| format!("No keycode for keysym {:#x}", keysym))
+        };
+        let (control, v) = (keycode(XK_CONTROL_L)?, keycode(XK_V)?);
+
+        for (kind, key) in [
+            (xproto::KEY_PRESS_EVENT, control),
+            (xproto::KEY_PRESS_EVENT, v),
+            (xproto::KEY_RELEASE_EVENT, v),
+            (xproto::KEY_RELEASE_EVENT, control),
+        ] {
+            conn.xtest_fake_input(kind, key, x11rb::CURRENT_TIME, root, 0, 0, 0)
+                .map_err(|e| e.to_string())?;
+        }
+        conn.sync().map_err(|e| e.to_string())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::xvfbTestLib::Xvfb;
+        use x11rb::protocol::xproto::{CreateWindowAux, KeyButMask, PropMode, WindowClass};
+        use x11rb::protocol::Event;
+
+        fn window(conn: &RustConnection, root: Window, events: EventMask) -> Window {
+            let window = conn.generate_id().unwrap();
+            conn.create_window(
+                0,
+                window,
+                root,
+                0,
+                0,
+                100,
+                100,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                0,
+                &CreateWindowAux::new().event_mask(events),
+            )
+            .unwrap();
+            conn.map_window(window).unwrap();
+            conn.sync().unwrap();
+            window
+        }
+
+        #[test]
+        fn reads_the_active_window_under_xvfb() {
+            let Some(xvfb) = Xvfb::start() else {
+                eprintln!("Xvfb is not installed; skipping");
+                return;
+            };
+            let display = Some(xvfb.display.as_str());
+            let (conn, root) = connect(display).unwrap();
+            assert_eq!(active_window(display).unwrap(), None);
+
+            let editor = window(&conn, root, EventMask::NO_EVENT);
+            let atom = active_atom(&conn).unwrap();
+            conn.change_property32(PropMode::REPLACE, root, atom, AtomEnum::WINDOW, &[editor])
+                .unwrap();
+            conn.sync().unwrap();
+            assert_eq!(active_window(display).unwrap(), Some(editor));
+
+            conn.change_property32(PropMode::REPLACE, root, atom, AtomEnum::WINDOW, &[0])
+                .unwrap();
+            conn.sync().unwrap();
+            assert_eq!(active_window(display).unwrap(), None);
+        }
+
+        #[test]
+        fn activates_and_pastes_without_a_window_manager() {
+            let Some(xvfb) = Xvfb::start() else {
+                eprintln!("Xvfb is not installed; skipping");
+                return;
+            };
+            let display = Some(xvfb.display.as_str());
+            let (conn, root) = connect(display).unwrap();
+            let editor = window(&conn, root, EventMask::KEY_PRESS);
+
+            activate(display, editor).unwrap();
+            let focus = conn.get_input_focus().unwrap().reply().unwrap().focus;
+            assert_eq!(focus, editor);
+
+            send_paste(display).unwrap();
+            let started = std::time::Instant::now();
+            let pressed = loop {
+                match conn.poll_for_event().unwrap() {
+                    Some(Event::KeyPress(press)) if press.state.contains(KeyButMask::CONTROL) => {
+                        break Some(press.detail)
+                    }
+                    Some(_) => {}
+                    None if started.elapsed() > std::time::Duration::from_secs(2) => break None,
+                    None => std::thread::sleep(std::time::Duration::from_millis(10)),
+                }
+            };
+            let keysyms = conn
+                .get_keyboard_mapping(pressed.expect("no Ctrl+key press arrived"), 1)
+                .unwrap()
+                .reply()
+                .unwrap()
+                .keysyms;
+            assert_eq!(keysyms.first(), Some(&XK_V));
+        }
+    }
+}
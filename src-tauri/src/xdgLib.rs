@@ -0,0 +1,44 @@
+// XDG base directories, used on Linux
+// https://specifications.freedesktop.org/basedir-spec/latest/
+
+use std::path::PathBuf;
+
+// Held by tests that point XDG variables at a temp dir
+#[cfg(test)]
+pub static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+// `$var` when set and non-empty, `$HOME/fallback` otherwise
+fn env_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)),
+    }
+}
+
+fn env_dirs(var: &str, fallback: &str) -> Vec<PathBuf> {
+    std::env::var(var)
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+        .split(':')
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// `$XDG_DATA_HOME`, defaulting to `~/.local/share`.
+pub fn data_home() -> Option<PathBuf> {
+    env_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// `$XDG_CONFIG_HOME`, defaulting to `~/.config`.
+pub fn config_home() -> Option<PathBuf> {
+    env_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn data_dirs() -> Vec<PathBuf> {
+    env_dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share")
+}
+
+pub fn config_dirs() -> Vec<PathBuf> {
+    env_dirs("XDG_CONFIG_DIRS", "/etc/xdg")
+}
@@ -1,5 +1,6 @@
 import { useState, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 type AppInfo = {
   name: string;
@@ -28,11 +29,38 @@ type BuiltInCommand = {
   calcResult?: string;
 };
 
+// A shutdown or restart counting down, as emitted by `pending-action`
+type PendingAction = {
+  id: number;
+  action: 'shutdown' | 'restart';
+  // Unix time in seconds
+  execute_at: number;
+  remaining: number;
+};
+
 type FileSearchItem = {
   name: string;
   path: string;
   action: () => void;
 };
+
+type Snippet = {
+  id: string;
+  title: string;
+  keyword: string | null;
+  tags: string[];
+  description: string | null;
+  language: string | null;
+  category: string | null;
+  content: string;
+};
+
+type FileMatch = {
+  path: string;
+  score: number;
+  indices: number[];
+  recent: boolean;
+};
 type SearchResult = AppInfo | BuiltInCommand | FileSearchItem;
 
 function isAppInfo(item: SearchResult): item is AppInfo {
@@ -334,6 +362,11 @@ export default function App() {
   const [langFrom, setLangFrom] = useState('');
   const [langTo, setLangTo] = useState('');
   const [translatedText, setTranslatedText] = useState('');
+  // Frecency of built-in commands by name, blended into their ranking
+  const [commandFrecency, setCommandFrecency] = useState<Record<string, number>>({});
+  const [pendingAction, setPendingAction] = useState<PendingAction | null>(null);
+  // Ticks every second while an action is pending, to redraw the countdown
+  const [now, setNow] = useState(() => Date.now());
 
   //TODO: REMOVE AFTER TESTING **********************************************************************************************************************************************************************
   localStorage.removeItem('usage_emoji')
@@ -357,14 +390,62 @@ export default function App() {
       .then(setApps)
       .catch((err) => console.error('Failed to fetch apps:', err));
     // Fetch snippets
-    invoke<[string, string][]>('get_snippets')
+    invoke<Snippet[]>('get_snippets')
       .then((data) => {
-        const formatted = data.map(([name, content]) => ({ name, content }));
+        const formatted = data.map(({ title, content }) => ({ name: title, content }));
         setSnippets(formatted);
       })
       .catch((err) => console.error('Failed to fetch snippets:', err));
+    fetchCommandFrecency();
+  }, []);
+
+  useEffect(() => {
+    invoke<PendingAction | null>('get_pending_action')
+      .then(setPendingAction)
+      .catch((err) => console.error('Failed to fetch pending action:', err));
+    const unlisten = [
+      listen<PendingAction>('pending-action', (event) => setPendingAction(event.payload)),
+      listen('pending-action-cancelled', () => setPendingAction(null)),
+      listen<string>('pending-action-failed', (event) => {
+        console.error('Pending action failed:', event.payload);
+        setPendingAction(null);
+      }),
+    ];
+    return () => {
+      unlisten.forEach((p) => p.then((stop) => stop()));
+    };
   }, []);
 
+  useEffect(() => {
+    if (!pendingAction) return;
+    const timer = setInterval(() => setNow(Date.now()), 1000);
+    return () => clearInterval(timer);
+  }, [pendingAction]);
+
+  function cancelPendingAction() {
+    invoke('cancel_pending_action')
+      .then(() => setPendingAction(null))
+      .catch(console.error);
+  }
+
+  function describePendingAction(pending: PendingAction): string {
+    const seconds = Math.max(0, pending.execute_at - Math.floor(now / 1000));
+    const clock = `${Math.floor(seconds / 60)}:${String(seconds % 60).padStart(2, '0')}`;
+    return `${pending.action === 'shutdown' ? 'Shutting down' : 'Restarting'} in ${clock}`;
+  }
+
+  function fetchCommandFrecency() {
+    invoke<Record<string, number>>('get_frecency_scores', { kind: 'command' })
+      .then(setCommandFrecency)
+      .catch((err) => console.error('Failed to fetch command usage:', err));
+  }
+
+  function recordCommand(name: string) {
+    invoke('record_usage', { kind: 'command', key: name })
+      .then(fetchCommandFrecency)
+      .catch((err) => console.error('Failed to record command usage:', err));
+  }
+
   useEffect(() => {
     if (query.trim() === '') {
       setCalcResult(null);
@@ -684,14 +765,27 @@ export default function App() {
     else if (q.startsWith('restart') || q.startsWith('reboot')) {
       allCommands.push({
         name: `Restart system`,
-        action: () => invoke('restart_system', {}).catch(console.error),
+        action: () =>
+          invoke<PendingAction | null>('restart_system', {})
+            .then(setPendingAction)
+            .catch(console.error),
       });
     }
     // System shutdown command 
     else if (q.startsWith('shutdown') || q.startsWith('power off')) {
       allCommands.push({
         name: `Shutdown system`,
-        action: () => invoke('shutdown_system', {}).catch(console.error),
+        action: () =>
+          invoke<PendingAction | null>('shutdown_system', {})
+            .then(setPendingAction)
+            .catch(console.error),
+      });
+    }
+    // Cancel a pending shutdown or restart
+    else if (q.startsWith('cancel') && pendingAction) {
+      allCommands.push({
+        name: `Cancel ${pendingAction.action}`,
+        action: cancelPendingAction,
       });
     }
     // System lock command 
@@ -735,12 +829,12 @@ export default function App() {
         allCommands.push({
           name: `Search local files for "${term}"`,
           action: () => {
-            invoke<string[]>('search_files', { query: term })
+            invoke<FileMatch[]>('search_files', { query: term })
               .then((results) => {
-                const items = results.map(path => ({
+                const items = results.map(({ path }) => ({
                   name: path,
                   path,
-                  action: () => invoke('launch_app', { appName: path }).catch(console.error),
+                  action: () => invoke('open_file_with', { path, app: null }).catch(console.error),
                 }));
                 setFileSearchResults(items);
               })
@@ -760,15 +854,23 @@ export default function App() {
       });
     }
 
-    // Filter commands by substring match, score by index of query in name (lower index better)
+    // Filter commands by substring match, score by index of query in name (lower index better),
+    // moving often and recently run commands ahead of close matches
+    const rank = (name: string, idx: number) => idx - Math.log1p(commandFrecency[name] ?? 0) * 4;
     const matchedCommands = allCommands
       .map(cmd => {
         const idx = cmd.name.toLowerCase().indexOf(q);
         return { cmd, idx };
       })
       .filter(({ idx }) => idx !== -1)
-      .sort((a, b) => a.idx - b.idx)
-      .map(({ cmd }) => cmd);
+      .sort((a, b) => rank(a.cmd.name, a.idx) - rank(b.cmd.name, b.idx))
+      .map(({ cmd }) => ({
+        ...cmd,
+        action: () => {
+          recordCommand(cmd.name);
+          cmd.action();
+        },
+      }));
 
     // TODO: Snippet insert command
     // snippets.forEach(({ name, content }) => {
@@ -888,7 +990,7 @@ export default function App() {
       setFiltered(newFiltered);
       setSelectedIndex(newFiltered.length > 0 ? 0 : -1);
     }
-  }, [query, apps, calcResult, emojiMode, specialCharsMode, fileSearchResults, clipboardMode, clipboardItems, snippets, snippetMode, translateMode, translateStep, langFrom, langTo]);
+  }, [query, apps, calcResult, emojiMode, specialCharsMode, fileSearchResults, clipboardMode, clipboardItems, snippets, snippetMode, translateMode, translateStep, langFrom, langTo, commandFrecency, pendingAction]);
 
   function onKeyDown(e: React.KeyboardEvent) {
     if (translateMode) {
@@ -1161,6 +1263,34 @@ export default function App() {
         }}
       />
 
+      {pendingAction && (
+        <div
+          style={{
+            display: 'flex',
+            alignItems: 'center',
+            justifyContent: 'space-between',
+            padding: '8px 15px',
+            marginBottom: 10,
+            borderRadius: 8,
+            backgroundColor: 'rgba(200, 60, 60, 0.25)',
+          }}
+        >
+          <span>{describePendingAction(pendingAction)}</span>
+          <button
+            onClick={cancelPendingAction}
+            style={{
+              backgroundColor: '#3a3a3a',
+              color: 'white',
+              border: 'none',
+              borderRadius: 6,
+              padding: '4px 12px',
+              cursor: 'pointer',
+            }}
+          >
+            Cancel
+          </button>
+        </div>
+      )}
 
       {(filtered.length > 0 && (query.length > 0 || emojiMode || clipboardMode || specialCharsMode)) && (
         <ul